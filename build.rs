@@ -2,7 +2,6 @@ use std::env;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
 
 fn main() {
     if let Err(err) = run() {
@@ -12,103 +11,55 @@ fn main() {
 }
 
 fn run() -> io::Result<()> {
-    let target_dir = find_target_dir()?;
-    prepare_assets(&target_dir)?;
-    Ok(())
-}
-
-/// Finds the Cargo `target` directory path from the `OUT_DIR` environment variable.
-/// Cargo sets `OUT_DIR` to a path like `.../target/debug/build/<pkg-name>-<hash>/out`.
-fn find_target_dir() -> io::Result<PathBuf> {
-    let out_dir = env::var("OUT_DIR").map_err(|_| {
-        io::Error::new(io::ErrorKind::Other, "OUT_DIR environment variable not set")
-    })?;
-
-    Path::new(&out_dir)
-        .ancestors()
-        .nth(3) // Traverse up three levels: out -> build -> profile -> target
-        .map(PathBuf::from)
-        .ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                "Failed to determine target directory from OUT_DIR",
-            )
-        })
-}
-
-/// Prepares runtime configuration assets.
-/// It creates the destination directory and copies each asset.
-fn prepare_assets(target_dir: &Path) -> io::Result<()> {
-    // Define the destination directory for config files within the `target` folder.
-    let config_dest = target_dir.join("config");
-    fs::create_dir_all(&config_dest)?;
-
-    // List of assets to be copied from `config/` in the project root.
-    const ASSETS: &[&str] = &[
-        ".wezterm.lua",
-        "stefc.zsh-theme",
-        "code.settings.json",
-        "yazi.theme.toml",
-        "helix.config.toml",
-    ];
-
-    const HELIX_ASSETS: &[&str] = &["warm-burnout-dark.toml", "warm-burnout-light.toml"];
-    const WEZTERM_ASSETS: &[&str] = &["warm-burnout-dark.toml", "warm-burnout-light.toml"];
-
-    copy_assets(&config_dest, None, ASSETS)?;
-    copy_assets(&config_dest, Some("helix-theme"), HELIX_ASSETS)?;
-    copy_assets(&config_dest, Some("wezterm-theme"), WEZTERM_ASSETS)?;
+    let out_dir = env::var("OUT_DIR")
+        .map_err(|_| io::Error::other("OUT_DIR environment variable not set"))?;
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| io::Error::other("CARGO_MANIFEST_DIR environment variable not set"))?;
 
-    Ok(())
+    let config_dir = Path::new(&manifest_dir).join("config");
+    generate_asset_registry(&config_dir, &Path::new(&out_dir).join("assets.rs"))
 }
 
-/// Copies a list of assets from `config/<subfolder>/` to `config_dest/<subfolder>/`,
-/// emitting `cargo:rerun-if-changed` directives for each asset.
-fn copy_assets(config_dest: &Path, subfolder: Option<&str>, assets: &[&str]) -> io::Result<()> {
-    for &asset_name in assets {
-        let (source_path, dest_path) = match subfolder {
-            Some(sub) => (
-                Path::new("config").join(sub).join(asset_name),
-                config_dest.join(sub).join(asset_name),
-            ),
-            None => (
-                Path::new("config").join(asset_name),
-                config_dest.join(asset_name),
-            ),
-        };
-
-        // Instruct Cargo to re-run this script if the source asset changes.
-        println!("cargo:rerun-if-changed={}", source_path.display());
-
-        copy_if_newer(&source_path, &dest_path)?;
+/// Generates the asset registry included by `src/assets.rs`.
+/// Every file below `config/` is embedded with `include_bytes!`, keyed by its
+/// path relative to `config/` using `/` as separator.
+fn generate_asset_registry(config_dir: &Path, dest: &Path) -> io::Result<()> {
+    // Instruct Cargo to re-run this script if anything in `config/` changes.
+    println!("cargo:rerun-if-changed={}", config_dir.display());
+
+    let mut files = Vec::new();
+    collect_files(config_dir, &mut files)?;
+    files.sort();
+
+    let mut registry = String::from("pub static ASSETS: &[Asset] = &[\n");
+    for file in &files {
+        let relative = file
+            .strip_prefix(config_dir)
+            .map_err(io::Error::other)?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        registry.push_str(&format!(
+            "    Asset {{ path: {:?}, contents: include_bytes!({:?}) }},\n",
+            relative,
+            file.display().to_string()
+        ));
     }
-    Ok(())
-}
+    registry.push_str("];\n");
 
-fn get_modified_time(path: &Path) -> io::Result<SystemTime> {
-    Ok(fs::metadata(path)?.modified()?)
+    fs::write(dest, registry)
 }
 
-fn copy_if_newer(source: &Path, dest: &Path) -> io::Result<bool> {
-    if !source.exists() {
-        return Ok(false);
-    }
-
-    let should_copy = if !dest.exists() {
-        true
-    } else {
-        let src_time = get_modified_time(source)?;
-        let dest_time = get_modified_time(dest)?;
-        src_time > dest_time
-    };
-
-    if should_copy {
-        if let Some(parent) = dest.parent() {
-            fs::create_dir_all(parent)?;
+/// Recursively collects all files below `dir`.
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
         }
-        fs::copy(source, dest)?;
-        Ok(true)
-    } else {
-        Ok(false)
     }
+    Ok(())
 }
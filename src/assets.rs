@@ -0,0 +1,68 @@
+use crate::common::{Log, SetupResult, replace_home_with_tilde};
+use std::{fs, io, path::Path};
+
+/// A config file embedded into the binary at build time
+pub struct Asset {
+    /// Path relative to the `config/` directory, using `/` as separator
+    pub path: &'static str,
+    pub contents: &'static [u8],
+}
+
+// Generated by build.rs from the `config/` tree
+include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+
+/// Look up an embedded asset by its path relative to `config/`
+pub fn find(path: &str) -> Option<&'static Asset> {
+    ASSETS.iter().find(|asset| asset.path == path)
+}
+
+/// Write every embedded asset below `dest`, returning the number of files written
+pub fn extract(dest: &Path) -> io::Result<usize> {
+    for asset in ASSETS {
+        let target = dest.join(asset.path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(target, asset.contents)?;
+    }
+    Ok(ASSETS.len())
+}
+
+/// Unpack the embedded assets into `dest`
+pub fn extract_assets(logger: &mut dyn Log, dest: &Path) -> SetupResult<()> {
+    logger.info("▶ Extract Assets");
+
+    let count = extract(dest)?;
+    for asset in ASSETS {
+        logger.ok_with_highlight("Extracted ->", asset.path);
+    }
+    logger.ok_with_highlight(
+        &format!("Extracted {} assets to ->", count),
+        &replace_home_with_tilde(dest),
+    );
+
+    logger.add_group("Assets", count);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::all_detectors;
+
+    #[test]
+    fn test_every_config_source_is_embedded() {
+        let config_dir = Path::new("config");
+        for detector in all_detectors() {
+            for source in detector.sources(config_dir) {
+                let relative = source.strip_prefix(config_dir).unwrap();
+                let relative = relative.to_string_lossy().replace('\\', "/");
+                assert!(
+                    ASSETS.iter().any(|asset| asset.path == relative),
+                    "{} is not embedded",
+                    relative
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use crate::common::{Log, SetupError, SetupResult, run_command};
+use crate::configurators::{Configurator, ZshrcConfigurator};
+use crate::detectors::which::is_program_in_path;
+use std::{
+    env, fs, io,
+    ops::Deref,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Slowdown of the median startup time reported as a regression
+const REGRESSION_THRESHOLD: f64 = 1.10;
+
+/// Number of entries shown in each profile ranking
+const TOP: usize = 5;
+
+/// Wall-clock times of repeated `zsh -i -c exit` runs
+pub struct Timing {
+    runs: Vec<Duration>,
+}
+
+impl Timing {
+    pub fn median(&self) -> Duration {
+        let mut sorted = self.runs.clone();
+        sorted.sort();
+        sorted.get(sorted.len() / 2).copied().unwrap_or_default()
+    }
+
+    fn describe(&self) -> String {
+        let min = self.runs.iter().min().copied().unwrap_or_default();
+        let max = self.runs.iter().max().copied().unwrap_or_default();
+        format!(
+            "median {} (min {}, max {}, {} runs)",
+            ms(self.median()),
+            ms(min),
+            ms(max),
+            self.runs.len()
+        )
+    }
+}
+
+/// Time in milliseconds spent per function (from zprof) and per oh-my-zsh plugin
+pub struct Profile {
+    pub functions: Vec<(String, f64)>,
+    pub plugins: Vec<(String, f64)>,
+}
+
+/// Time zsh startup, apply the .zshrc changes and time it again, reporting a
+/// regression when startup became noticeably slower
+pub fn bench(
+    logger: &mut dyn Log,
+    zshrc: &ZshrcConfigurator,
+    runs: usize,
+    profile: bool,
+) -> SetupResult<()> {
+    logger.info("▶ Startup benchmark");
+    if !is_program_in_path("zsh") {
+        logger.warn("zsh is not installed, nothing to benchmark");
+        return Ok(());
+    }
+
+    // `measure` starts zsh at least once
+    let runs = runs.max(1);
+    let before = measure(runs)?;
+    logger.info(&format!("Before: {}", before.describe()));
+    zshrc.run(logger)?;
+    let after = measure(runs)?;
+    logger.info(&format!("After: {}", after.describe()));
+
+    let ratio = after.median().as_secs_f64() / before.median().as_secs_f64().max(f64::EPSILON);
+    if ratio > REGRESSION_THRESHOLD {
+        logger.warn(&format!(
+            "Startup regressed by {:.0}% ({} -> {})",
+            (ratio - 1.0) * 100.0,
+            ms(before.median()),
+            ms(after.median())
+        ));
+    } else {
+        logger.ok_with_highlight("Startup time ->", &ms(after.median()));
+    }
+
+    if profile {
+        let profile = self::profile(&ZshrcConfigurator::zdotdir()?)?;
+        for (name, cost) in profile.plugins.iter().take(TOP) {
+            logger.info(&format!("  plugin {:<24} {:>8.1} ms", name, cost));
+        }
+        for (name, cost) in profile.functions.iter().take(TOP) {
+            logger.info(&format!("  function {:<22} {:>8.1} ms", name, cost));
+        }
+    }
+    logger.add_group("Benchmark runs", runs * 2);
+    Ok(())
+}
+
+/// Time `runs` interactive zsh startups
+pub fn measure(runs: usize) -> SetupResult<Timing> {
+    let mut timing = Timing { runs: Vec::new() };
+    for _ in 0..runs.max(1) {
+        let start = Instant::now();
+        run_command("zsh", &["-i", "-c", "exit"])?;
+        timing.runs.push(start.elapsed());
+    }
+    Ok(timing)
+}
+
+/// Run one traced, zprof-instrumented startup. A temporary `ZDOTDIR` loads
+/// zprof and turns on timestamped tracing before handing over to the real startup files.
+pub fn profile(zdotdir: &Path) -> SetupResult<Profile> {
+    // The trace holds every exported value, so it lives in a private directory
+    let dir = PrivateDir::create("mac-setup-zprof")?;
+    let trace = dir.join("trace");
+    let zshenv = format!(
+        "zmodload zsh/zprof\n\
+         zmodload zsh/datetime\n\
+         PS4='+$EPOCHREALTIME %x> '\n\
+         exec 2>\"{trace}\"\n\
+         setopt xtrace prompt_subst\n\
+         ZDOTDIR=\"{orig}\"\n\
+         [[ -r \"$ZDOTDIR/.zshenv\" ]] && source \"$ZDOTDIR/.zshenv\"\n",
+        trace = trace.display(),
+        orig = zdotdir.display(),
+    );
+    fs::write(dir.join(".zshenv"), zshenv)?;
+
+    let output = std::process::Command::new("zsh")
+        .args(["-i", "-c", "unsetopt xtrace; zprof"])
+        .env("ZDOTDIR", &*dir)
+        .output()?;
+    let trace = fs::read_to_string(&trace).unwrap_or_default();
+    drop(dir);
+    if !output.status.success() {
+        return Err(SetupError::CommandFailed {
+            command: "zsh -i -c zprof".to_string(),
+            exit_code: output.status.code(),
+        });
+    }
+
+    Ok(Profile {
+        functions: parse_zprof(&String::from_utf8_lossy(&output.stdout)),
+        plugins: plugin_costs(&trace),
+    })
+}
+
+/// Temporary directory only the current user can read, removed when dropped
+struct PrivateDir(PathBuf);
+
+impl PrivateDir {
+    /// Create a new directory; an existing one with the same name is never reused
+    fn create(prefix: &str) -> io::Result<Self> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos();
+        let dir = env::temp_dir().join(format!("{}-{}-{}", prefix, std::process::id(), nanos));
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(&dir)?;
+        Ok(Self(dir))
+    }
+}
+
+impl Deref for PrivateDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for PrivateDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Self time per function from the first table of `zprof` output, most expensive first
+fn parse_zprof(output: &str) -> Vec<(String, f64)> {
+    let mut functions: Vec<(String, f64)> = output
+        .lines()
+        .take_while(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            // num) calls total avg pct self avg pct name
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 9 || !fields[0].ends_with(')') {
+                return None;
+            }
+            Some((fields[8].to_string(), fields[5].parse().ok()?))
+        })
+        .collect();
+    sort_by_cost(&mut functions);
+    functions
+}
+
+/// Time spent in each oh-my-zsh plugin, attributing the gap between two trace
+/// lines to the file the first one came from
+fn plugin_costs(trace: &str) -> Vec<(String, f64)> {
+    let events: Vec<(f64, &str)> = trace
+        .lines()
+        .filter_map(|line| {
+            let (stamp, rest) = line.strip_prefix('+')?.split_once(' ')?;
+            let (file, _) = rest.split_once("> ")?;
+            Some((stamp.parse().ok()?, file))
+        })
+        .collect();
+
+    let mut plugins: Vec<(String, f64)> = Vec::new();
+    for pair in events.windows(2) {
+        let ((start, file), (end, _)) = (pair[0], pair[1]);
+        let Some(plugin) = file
+            .split_once("/plugins/")
+            .and_then(|(_, rest)| rest.split('/').next())
+        else {
+            continue;
+        };
+        let cost = (end - start).max(0.0) * 1000.0;
+        match plugins.iter_mut().find(|(name, _)| name == plugin) {
+            Some((_, total)) => *total += cost,
+            None => plugins.push((plugin.to_string(), cost)),
+        }
+    }
+    sort_by_cost(&mut plugins);
+    plugins
+}
+
+fn sort_by_cost(entries: &mut [(String, f64)]) {
+    entries.sort_by(|a, b| b.1.total_cmp(&a.1));
+}
+
+fn ms(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_zprof_first_table() {
+        let output = "\
+num  calls                time                       self            name
+-----------------------------------------------------------------------------------
+ 1)    1          20.50    20.50   60.00%     15.00    15.00   44.00%  compinit
+ 2)    4           5.00     1.25   15.00%      5.00     1.25   15.00%  _omz_source
+
+-----------------------------------------------------------------------------------
+
+ 1)    1          20.50    20.50   60.00%     15.00    15.00   44.00%  compinit
+";
+        assert_eq!(
+            parse_zprof(output),
+            vec![
+                ("compinit".to_string(), 15.0),
+                ("_omz_source".to_string(), 5.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plugin_costs_from_trace() {
+        let trace = "\
++1.000 /home/u/.oh-my-zsh/oh-my-zsh.sh> for plugin in $plugins
++1.010 /home/u/.oh-my-zsh/plugins/git/git.plugin.zsh> autoload -Uz git-commit
+continued line of a multi-line command
++1.030 /home/u/.oh-my-zsh/custom/plugins/zsh-autosuggestions/zsh-autosuggestions.plugin.zsh> source x
++1.080 /home/u/.zshrc> export EDITOR=hx
+";
+        let costs = plugin_costs(trace);
+        assert_eq!(costs[0].0, "zsh-autosuggestions");
+        assert!((costs[0].1 - 50.0).abs() < 0.01);
+        assert_eq!(costs[1].0, "git");
+        assert!((costs[1].1 - 20.0).abs() < 0.01);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_private_dir_is_owner_only_and_removed() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = PrivateDir::create("mac-setup-test-private").unwrap();
+        let path = dir.to_path_buf();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        drop(dir);
+        assert!(!path.exists());
+    }
+}
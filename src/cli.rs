@@ -0,0 +1,204 @@
+use crate::common::{EditMode, SetupError, SetupResult};
+use std::path::PathBuf;
+
+const USAGE: &str = "usage: mac-setup [--config-dir <dir>] [--plan | --interactive] [setup | verify | repair | install | extract <dir> | update-plugins | lint [--fix] | bench [--runs <n>] [--profile]]";
+
+/// Action selected on the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Apply system settings, run configurators and create symlinks (default)
+    Setup,
+    /// Report the health of every known symlink without changing anything
+    Verify,
+    /// Re-point dangling, stale or mistyped symlinks
+    Repair,
+    /// Copy the binary and config assets to the data directory and re-point symlinks there
+    Install,
+    /// Unpack the config assets embedded in the binary into a directory
+    Extract(PathBuf),
+    /// Fetch external zsh plugins and check out their configured refs
+    UpdatePlugins,
+    /// Report problems in .zshrc, applying the safe fixes when `fix` is set
+    Lint { fix: bool },
+    /// Time zsh startup before and after configuring .zshrc, optionally profiling it
+    Bench { runs: usize, profile: bool },
+}
+
+/// Startup runs timed by `bench` unless `--runs` is given
+pub const DEFAULT_BENCH_RUNS: usize = 10;
+
+/// Parsed command line
+#[derive(Debug, Clone)]
+pub struct Cli {
+    pub command: Command,
+    pub config_dir: Option<PathBuf>,
+    /// Whether file edits are written, only shown, or confirmed one by one
+    pub mode: EditMode,
+}
+
+impl Cli {
+    /// Parse the program arguments (without the program name)
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> SetupResult<Self> {
+        let mut command = None;
+        let mut config_dir = None;
+        let mut mode = EditMode::Apply;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config-dir" => {
+                    let dir = args
+                        .next()
+                        .ok_or_else(|| usage_error(format!("'{}' requires a directory", arg)))?;
+                    config_dir = Some(PathBuf::from(dir));
+                }
+                _ if arg.starts_with("--config-dir=") => {
+                    config_dir = Some(PathBuf::from(&arg["--config-dir=".len()..]));
+                }
+                "--plan" | "--interactive" => {
+                    if mode != EditMode::Apply {
+                        return Err(usage_error(
+                            "'--plan' and '--interactive' exclude each other".to_string(),
+                        ));
+                    }
+                    mode = if arg == "--plan" {
+                        EditMode::Plan
+                    } else {
+                        EditMode::Interactive
+                    };
+                }
+                "--fix" if command == Some(Command::Lint { fix: false }) => {
+                    command = Some(Command::Lint { fix: true });
+                }
+                "--profile" if matches!(command, Some(Command::Bench { .. })) => {
+                    if let Some(Command::Bench { profile, .. }) = &mut command {
+                        *profile = true;
+                    }
+                }
+                "--runs" if matches!(command, Some(Command::Bench { .. })) => {
+                    let count = args
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|n| *n > 0)
+                        .ok_or_else(|| {
+                            usage_error("'--runs' requires a positive number".to_string())
+                        })?;
+                    if let Some(Command::Bench { runs, .. }) = &mut command {
+                        *runs = count;
+                    }
+                }
+                _ if command.is_some() => {
+                    return Err(usage_error(format!("unexpected argument '{}'", arg)));
+                }
+                "setup" => command = Some(Command::Setup),
+                "verify" => command = Some(Command::Verify),
+                "repair" => command = Some(Command::Repair),
+                "install" => command = Some(Command::Install),
+                "extract" => {
+                    let dir = args
+                        .next()
+                        .ok_or_else(|| usage_error("'extract' requires a directory".to_string()))?;
+                    command = Some(Command::Extract(PathBuf::from(dir)));
+                }
+                "update-plugins" => command = Some(Command::UpdatePlugins),
+                "lint" => command = Some(Command::Lint { fix: false }),
+                "bench" => {
+                    command = Some(Command::Bench {
+                        runs: DEFAULT_BENCH_RUNS,
+                        profile: false,
+                    })
+                }
+                _ => return Err(usage_error(format!("unknown command '{}'", arg))),
+            }
+        }
+
+        Ok(Cli {
+            command: command.unwrap_or(Command::Setup),
+            config_dir,
+            mode,
+        })
+    }
+}
+
+fn usage_error(msg: String) -> SetupError {
+    SetupError::InvalidArguments(format!("{} ({})", msg, USAGE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> SetupResult<Cli> {
+        Cli::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_defaults_to_setup() {
+        let cli = parse(&[]).unwrap();
+        assert_eq!(cli.command, Command::Setup);
+        assert!(cli.config_dir.is_none());
+    }
+
+    #[test]
+    fn test_parse_config_dir_flag() {
+        let cli = parse(&["--config-dir", "/tmp/cfg", "repair"]).unwrap();
+        assert_eq!(cli.command, Command::Repair);
+        assert_eq!(cli.config_dir, Some(PathBuf::from("/tmp/cfg")));
+
+        let cli = parse(&["install", "--config-dir=/tmp/cfg"]).unwrap();
+        assert_eq!(cli.command, Command::Install);
+        assert_eq!(cli.config_dir, Some(PathBuf::from("/tmp/cfg")));
+    }
+
+    #[test]
+    fn test_parse_edit_mode() {
+        assert_eq!(parse(&[]).unwrap().mode, EditMode::Apply);
+        assert_eq!(parse(&["--plan"]).unwrap().mode, EditMode::Plan);
+        let cli = parse(&["lint", "--fix", "--interactive"]).unwrap();
+        assert_eq!(cli.mode, EditMode::Interactive);
+        assert!(parse(&["--plan", "--interactive"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_bench_options() {
+        let cli = parse(&["bench"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Command::Bench {
+                runs: DEFAULT_BENCH_RUNS,
+                profile: false
+            }
+        );
+        let cli = parse(&["bench", "--profile", "--runs", "3"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Command::Bench {
+                runs: 3,
+                profile: true
+            }
+        );
+        assert!(parse(&["bench", "--runs", "0"]).is_err());
+        assert!(parse(&["--profile"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_extract_directory() {
+        let cli = parse(&["extract", "out"]).unwrap();
+        assert_eq!(cli.command, Command::Extract(PathBuf::from("out")));
+
+        let cli = parse(&["update-plugins"]).unwrap();
+        assert_eq!(cli.command, Command::UpdatePlugins);
+
+        let cli = parse(&["lint", "--fix"]).unwrap();
+        assert_eq!(cli.command, Command::Lint { fix: true });
+        assert!(parse(&["--fix", "lint"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_arguments() {
+        assert!(parse(&["frobnicate"]).is_err());
+        assert!(parse(&["verify", "repair"]).is_err());
+        assert!(parse(&["--config-dir"]).is_err());
+        assert!(parse(&["extract"]).is_err());
+    }
+}
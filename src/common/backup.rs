@@ -0,0 +1,117 @@
+use super::{SetupResult, write_atomic};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Snapshot of a path taken before it is modified, used to roll back a failed change
+pub enum Backup {
+    /// Nothing existed at the path
+    Absent(PathBuf),
+    /// A regular file with its previous contents
+    File(PathBuf, Vec<u8>),
+    /// A symlink with its previous target
+    Symlink(PathBuf, PathBuf),
+}
+
+impl Backup {
+    /// Record the current state of `path`
+    pub fn capture(path: &Path) -> io::Result<Self> {
+        let path = path.to_path_buf();
+        match fs::symlink_metadata(&path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Backup::Absent(path)),
+            Err(e) => Err(e),
+            Ok(meta) if meta.file_type().is_symlink() => {
+                let target = fs::read_link(&path)?;
+                Ok(Backup::Symlink(path, target))
+            }
+            Ok(meta) if meta.is_dir() => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{} is a directory", path.display()),
+            )),
+            Ok(_) => {
+                let contents = fs::read(&path)?;
+                Ok(Backup::File(path, contents))
+            }
+        }
+    }
+
+    /// Put the recorded state back in place
+    pub fn restore(&self) -> io::Result<()> {
+        let path = match self {
+            // Renamed over whatever is there, keeping the mode of the current file
+            Backup::File(path, contents) => return write_atomic(path, contents),
+            Backup::Absent(path) | Backup::Symlink(path, _) => path,
+        };
+        if fs::symlink_metadata(path).is_ok() {
+            fs::remove_file(path)?;
+        }
+        match self {
+            Backup::Symlink(path, target) => symlink(target, path),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Apply a change to `path`, then validate it; restore the previous state if either step fails
+pub fn apply_with_rollback(
+    path: &Path,
+    apply: impl FnOnce() -> SetupResult<()>,
+    validate: impl FnOnce() -> SetupResult<()>,
+) -> SetupResult<()> {
+    let backup = Backup::capture(path)?;
+    let result = apply().and_then(|_| validate());
+    if result.is_err() {
+        backup.restore()?;
+    }
+    result
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, path: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, path)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, path: &Path) -> io::Result<()> {
+    if target.is_dir() {
+        std::os::windows::fs::symlink_dir(target, path)
+    } else {
+        std::os::windows::fs::symlink_file(target, path)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::common::{SetupError, TestDir};
+
+    #[test]
+    fn test_apply_with_rollback_restores_file_and_link() {
+        let dir = TestDir::new("backup");
+
+        let file = dir.join("rc");
+        fs::write(&file, "old").unwrap();
+        let result = apply_with_rollback(
+            &file,
+            || Ok(fs::write(&file, "new")?),
+            || Err(SetupError::Validation("bad".to_string())),
+        );
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "old");
+
+        let link = dir.join("link");
+        symlink(&file, &link).unwrap();
+        let backup = Backup::capture(&link).unwrap();
+        fs::remove_file(&link).unwrap();
+        fs::write(&link, "replaced").unwrap();
+        backup.restore().unwrap();
+        assert_eq!(fs::read_link(&link).unwrap(), file);
+
+        let missing = dir.join("missing");
+        let backup = Backup::capture(&missing).unwrap();
+        fs::write(&missing, "created").unwrap();
+        backup.restore().unwrap();
+        assert!(!missing.exists());
+    }
+}
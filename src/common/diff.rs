@@ -0,0 +1,161 @@
+/// Lines of context shown around each change
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Unified diff between `old` and `new`, labelled with `old_label` and
+/// `new_label`. Returns no lines when the contents are the same.
+pub fn unified(old: &str, new: &str, old_label: &str, new_label: &str) -> Vec<String> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let ops = diff_ops(&old, &new);
+    if ops.iter().all(|(op, _, _)| *op == Op::Equal) {
+        return Vec::new();
+    }
+
+    let mut out = vec![format!("--- {}", old_label), format!("+++ {}", new_label)];
+    let mut i = 0;
+    while i < ops.len() {
+        // Find the next change and the end of its hunk
+        let Some(first) = ops[i..].iter().position(|(op, _, _)| *op != Op::Equal) else {
+            break;
+        };
+        let start = (i + first).saturating_sub(CONTEXT);
+        let mut end = i + first;
+        let mut last_change = end;
+        while end < ops.len() {
+            if ops[end].0 != Op::Equal {
+                last_change = end;
+            } else if end - last_change > 2 * CONTEXT {
+                break;
+            }
+            end += 1;
+        }
+        let end = (last_change + CONTEXT + 1).min(ops.len());
+
+        let hunk = &ops[start..end];
+        let old_count = hunk.iter().filter(|(op, _, _)| *op != Op::Insert).count();
+        let new_count = hunk.iter().filter(|(op, _, _)| *op != Op::Delete).count();
+        let (_, old_start, new_start) = hunk[0];
+        out.push(format!(
+            "@@ -{} +{} @@",
+            range(old_start, old_count),
+            range(new_start, new_count)
+        ));
+        for &(op, o, n) in hunk {
+            out.push(match op {
+                Op::Equal => format!(" {}", old[o]),
+                Op::Delete => format!("-{}", old[o]),
+                Op::Insert => format!("+{}", new[n]),
+            });
+        }
+        i = end;
+    }
+    out
+}
+
+/// Hunk range in `start,count` form with 1-based lines; an empty range names the line before it
+fn range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+/// Edit script as `(op, old index, new index)`, where the indexes give the
+/// position in each file the op applies to
+fn diff_ops(old: &[&str], new: &[&str]) -> Vec<(Op, usize, usize)> {
+    // Common prefix and suffix are cheap to skip and usually cover most of a file
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    // Longest common subsequence table over the differing middle
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops: Vec<(Op, usize, usize)> = (0..prefix).map(|k| (Op::Equal, k, k)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        let (o, n) = (prefix + i, prefix + j);
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((Op::Equal, o, n));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Deletions first, so a replaced line reads as `-old` then `+new`
+            ops.push((Op::Delete, o, n));
+            i += 1;
+        } else {
+            ops.push((Op::Insert, o, n));
+            j += 1;
+        }
+    }
+    let (o, n) = (old.len() - suffix, new.len() - suffix);
+    ops.extend((0..suffix).map(|k| (Op::Equal, o + k, n + k)));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_content_has_no_diff() {
+        assert!(unified("a\nb\n", "a\nb\n", "old", "new").is_empty());
+    }
+
+    #[test]
+    fn test_unified_hunks_with_context() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+        let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n";
+        assert_eq!(
+            unified(old, new, "a/.zshrc", "b/.zshrc"),
+            vec![
+                "--- a/.zshrc",
+                "+++ b/.zshrc",
+                "@@ -1,6 +1,6 @@",
+                " 1",
+                " 2",
+                "-3",
+                "+three",
+                " 4",
+                " 5",
+                " 6",
+                "@@ -10,3 +10,4 @@",
+                " 10",
+                " 11",
+                " 12",
+                "+13",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_against_empty_file() {
+        assert_eq!(
+            unified("", "a\n", "/dev/null", "new"),
+            vec!["--- /dev/null", "+++ new", "@@ -0,0 +1 @@", "+a"]
+        );
+    }
+}
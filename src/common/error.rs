@@ -8,6 +8,14 @@ pub enum SetupError {
         command: String,
         exit_code: Option<i32>,
     },
+    #[error("Invalid arguments: {0}")]
+    InvalidArguments(String),
+    #[error("Config directory not found: {0}")]
+    ConfigDirNotFound(String),
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+    #[error("Validation failed: {0}")]
+    Validation(String),
     #[error("IO error: {0}")]
     Io(#[from] IoError),
 }
@@ -0,0 +1,388 @@
+//! Minimal JSON-with-comments parser, as used by VS Code settings files.
+//! Accepts `//` and `/* */` comments as well as trailing commas, and records
+//! byte offsets so callers can report errors or edit the source in place.
+
+use std::fmt;
+
+/// A parsed JSONC value
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    /// Numbers are kept in their source form
+    Number(String),
+    String(String),
+    Array(Vec<JsonNode>),
+    Object(Vec<JsonMember>),
+}
+
+/// A value together with its byte range in the source text
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonNode {
+    pub value: JsonValue,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl JsonNode {
+    /// Whether the node is an object or array without entries
+    pub fn is_empty(&self) -> bool {
+        match &self.value {
+            JsonValue::Array(items) => items.is_empty(),
+            JsonValue::Object(members) => members.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Whether both nodes hold the same value, wherever they appear in their sources
+    pub fn same_value(&self, other: &JsonNode) -> bool {
+        match (&self.value, &other.value) {
+            (JsonValue::Number(a), JsonValue::Number(b)) => {
+                a == b || a.parse::<f64>().ok() == b.parse::<f64>().ok()
+            }
+            (JsonValue::Array(a), JsonValue::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.same_value(b))
+            }
+            (JsonValue::Object(a), JsonValue::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter().all(|member| {
+                        b.iter()
+                            .rfind(|other| other.key == member.key)
+                            .is_some_and(|other| member.value.same_value(&other.value))
+                    })
+            }
+            (a, b) => a == b,
+        }
+    }
+}
+
+/// An object member; `start` is the offset of the opening quote of the key
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonMember {
+    pub key: String,
+    pub start: usize,
+    pub value: JsonNode,
+}
+
+/// Parse failure with a 1-based line and column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+/// Parse a complete JSONC document
+pub fn parse(text: &str) -> Result<JsonNode, JsonError> {
+    let mut parser = Parser { text, pos: 0 };
+    parser.skip_trivia()?;
+    let node = parser.value()?;
+    parser.skip_trivia()?;
+    if parser.pos < text.len() {
+        return Err(parser.error("unexpected content after the document"));
+    }
+    Ok(node)
+}
+
+/// Convert a byte offset into a 1-based line and column
+pub fn line_col(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+    (line, column)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> JsonError {
+        let (line, column) = line_col(self.text, self.pos);
+        JsonError {
+            line,
+            column,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    /// Skip whitespace and comments
+    fn skip_trivia(&mut self) -> Result<(), JsonError> {
+        loop {
+            match self.peek() {
+                Some(b' ' | b'\t' | b'\n' | b'\r') => self.pos += 1,
+                Some(b'/') if self.rest().starts_with("//") => {
+                    self.pos = self
+                        .rest()
+                        .find('\n')
+                        .map_or(self.text.len(), |i| self.pos + i);
+                }
+                Some(b'/') if self.rest().starts_with("/*") => match self.rest()[2..].find("*/") {
+                    Some(i) => self.pos += i + 4,
+                    None => return Err(self.error("unterminated block comment")),
+                },
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<JsonNode, JsonError> {
+        let start = self.pos;
+        let value = match self.peek() {
+            Some(b'{') => self.object()?,
+            Some(b'[') => self.array()?,
+            Some(b'"') => JsonValue::String(self.string()?),
+            Some(b'-' | b'0'..=b'9') => self.number()?,
+            Some(_) if self.rest().starts_with("true") => {
+                self.keyword("true", JsonValue::Bool(true))
+            }
+            Some(_) if self.rest().starts_with("false") => {
+                self.keyword("false", JsonValue::Bool(false))
+            }
+            Some(_) if self.rest().starts_with("null") => self.keyword("null", JsonValue::Null),
+            Some(_) => return Err(self.error("expected a value")),
+            None => return Err(self.error("unexpected end of input")),
+        };
+        Ok(JsonNode {
+            value,
+            start,
+            end: self.pos,
+        })
+    }
+
+    fn keyword(&mut self, word: &str, value: JsonValue) -> JsonValue {
+        self.pos += word.len();
+        value
+    }
+
+    fn number(&mut self) -> Result<JsonValue, JsonError> {
+        let start = self.pos;
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        let literal = &self.text[start..self.pos];
+        if !is_number(literal) {
+            self.pos = start;
+            return Err(self.error("invalid number"));
+        }
+        Ok(JsonValue::Number(literal.to_string()))
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let Some(c) = self.rest().chars().next() else {
+                self.pos = start;
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(out),
+                '\n' => {
+                    self.pos = start;
+                    return Err(self.error("unterminated string"));
+                }
+                '\\' => {
+                    let Some(escaped) = self.rest().chars().next() else {
+                        return Err(self.error("unterminated escape sequence"));
+                    };
+                    self.pos += escaped.len_utf8();
+                    match escaped {
+                        '"' | '\\' | '/' => out.push(escaped),
+                        'b' => out.push('\u{8}'),
+                        'f' => out.push('\u{c}'),
+                        'n' => out.push('\n'),
+                        'r' => out.push('\r'),
+                        't' => out.push('\t'),
+                        'u' => {
+                            let mut code = self.hex4()?;
+                            // A high surrogate is combined with the low surrogate escape after it
+                            if (0xD800..0xDC00).contains(&code) && self.rest().starts_with("\\u") {
+                                let pos = self.pos;
+                                self.pos += 2;
+                                match self.hex4()? {
+                                    low @ 0xDC00..0xE000 => {
+                                        code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                                    }
+                                    _ => self.pos = pos,
+                                }
+                            }
+                            out.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                        }
+                        _ => return Err(self.error("invalid escape sequence")),
+                    }
+                }
+                _ => out.push(c),
+            }
+        }
+    }
+
+    /// Four hex digits of a `\u` escape
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let hex = self.rest().get(..4).unwrap_or_default();
+        if hex.len() != 4 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(self.error("invalid unicode escape"));
+        }
+        let code = u32::from_str_radix(hex, 16).unwrap_or_default();
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn object(&mut self) -> Result<JsonValue, JsonError> {
+        self.pos += 1;
+        let mut members = Vec::new();
+        loop {
+            self.skip_trivia()?;
+            match self.peek() {
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Object(members));
+                }
+                Some(b'"') => {}
+                _ => return Err(self.error("expected a key or '}'")),
+            }
+            let start = self.pos;
+            let key = self.string()?;
+            self.skip_trivia()?;
+            if self.peek() != Some(b':') {
+                return Err(self.error("expected ':'"));
+            }
+            self.pos += 1;
+            self.skip_trivia()?;
+            let value = self.value()?;
+            members.push(JsonMember { key, start, value });
+            self.skip_trivia()?;
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {}
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<JsonValue, JsonError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia()?;
+            if self.peek() == Some(b']') {
+                self.pos += 1;
+                return Ok(JsonValue::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_trivia()?;
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {}
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+}
+
+/// Whether `literal` follows the JSON number grammar: `-?(0|[1-9][0-9]*)(.[0-9]+)?([eE][+-]?[0-9]+)?`
+fn is_number(literal: &str) -> bool {
+    fn digits(s: &str) -> (&str, &str) {
+        let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        s.split_at(end)
+    }
+
+    let rest = literal.strip_prefix('-').unwrap_or(literal);
+    let (int, mut rest) = digits(rest);
+    if int.is_empty() || (int.len() > 1 && int.starts_with('0')) {
+        return false;
+    }
+    if let Some(after) = rest.strip_prefix('.') {
+        let (fraction, after) = digits(after);
+        if fraction.is_empty() {
+            return false;
+        }
+        rest = after;
+    }
+    if let Some(after) = rest.strip_prefix(['e', 'E']) {
+        let (exponent, after) = digits(after.strip_prefix(['+', '-']).unwrap_or(after));
+        if exponent.is_empty() {
+            return false;
+        }
+        rest = after;
+    }
+    rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_comments_and_trailing_commas() {
+        let text = "{\n  // comment\n  \"a\": [1, 2,],\n  /* block */ \"b\": {\"c\": null},\n}";
+        let node = parse(text).unwrap();
+        let JsonValue::Object(members) = node.value else {
+            panic!("expected an object");
+        };
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].key, "a");
+        assert_eq!(
+            &text[members[0].value.start..members[0].value.end],
+            "[1, 2,]"
+        );
+        assert_eq!(members[1].key, "b");
+    }
+
+    #[test]
+    fn test_parse_reports_line_and_column() {
+        let err = parse("{\n  \"a\": 1\n  \"b\": 2\n}").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 3));
+    }
+
+    #[test]
+    fn test_same_value_ignores_layout_and_key_order() {
+        let a = parse("{\"a\": [1, 2.0], \"b\": true}").unwrap();
+        let b = parse("{\n  // reordered\n  \"b\": true,\n  \"a\": [1, 2],\n}").unwrap();
+        assert!(a.same_value(&b));
+        assert!(!a.same_value(&parse("{\"a\": [1], \"b\": true}").unwrap()));
+    }
+
+    #[test]
+    fn test_parse_string_escapes() {
+        let node = parse(r#""a\"b\u0041""#).unwrap();
+        assert_eq!(node.value, JsonValue::String("a\"bA".to_string()));
+        let node = parse(r#""\uD83D\uDE00 \uD83D""#).unwrap();
+        assert_eq!(
+            node.value,
+            JsonValue::String("\u{1F600} \u{FFFD}".to_string())
+        );
+        assert!(parse(r#""\u+041""#).is_err());
+    }
+
+    #[test]
+    fn test_parse_numbers_follow_json_grammar() {
+        for valid in ["0", "-0", "12", "1.5", "-1.5e10", "2E-3", "0.0e+1"] {
+            assert!(parse(valid).is_ok(), "{}", valid);
+        }
+        for invalid in ["01", "1.", ".5", "+1", "-", "1e", "1.e3", "--1"] {
+            assert!(parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
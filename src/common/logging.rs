@@ -1,11 +1,11 @@
-use std::io;
+use std::io::{self, IsTerminal, Write};
 
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Constraint, Direction, Layout};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph};
+use ratatui::widgets::{Block, Borders, LineGauge, List, ListItem, Paragraph};
 
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
 
@@ -13,7 +13,11 @@ pub trait Log {
     fn info(&mut self, msg: &str);
     fn warn(&mut self, msg: &str);
     fn ok_with_highlight(&mut self, msg: &str, highlight: &str);
+    /// Record the lines of a unified diff
+    fn diff(&mut self, lines: &[String]);
     fn add_group(&mut self, title: &str, affected_count: usize);
+    /// Report that `done` of `total` items of a long-running step are finished
+    fn progress(&mut self, title: &str, done: usize, total: usize);
     fn snapshot(&self) -> LogSnapshot;
 }
 
@@ -21,6 +25,7 @@ pub trait Log {
 pub struct MemoryLogger {
     lines: Vec<LogLine>,
     groups: Vec<GroupSummary>,
+    progress: Vec<Progress>,
 }
 
 #[derive(Clone)]
@@ -36,11 +41,19 @@ pub struct GroupSummary {
     pub affected_count: usize,
 }
 
+#[derive(Clone)]
+pub struct Progress {
+    pub title: String,
+    pub done: usize,
+    pub total: usize,
+}
+
 #[derive(Clone)]
 pub enum LogLevel {
     Info,
     Ok,
     Warn,
+    Diff,
 }
 
 impl Log for MemoryLogger {
@@ -66,6 +79,14 @@ impl Log for MemoryLogger {
         });
     }
 
+    fn diff(&mut self, lines: &[String]) {
+        self.lines.extend(lines.iter().map(|line| LogLine {
+            level: LogLevel::Diff,
+            msg: line.clone(),
+            highlight: None,
+        }));
+    }
+
     fn add_group(&mut self, title: &str, affected_count: usize) {
         self.groups.push(GroupSummary {
             title: title.to_string(),
@@ -73,10 +94,29 @@ impl Log for MemoryLogger {
         });
     }
 
+    fn progress(&mut self, title: &str, done: usize, total: usize) {
+        match self.progress.iter_mut().find(|p| p.title == title) {
+            Some(progress) => progress.done = done,
+            None => self.progress.push(Progress {
+                title: title.to_string(),
+                done,
+                total,
+            }),
+        }
+        // The summary is drawn once everything finished, so show a live status line until then
+        let mut stderr = io::stderr();
+        if stderr.is_terminal() {
+            let end = if done >= total { "\n" } else { "" };
+            let _ = write!(stderr, "\r{} {}/{}{}", title, done, total, end);
+            let _ = stderr.flush();
+        }
+    }
+
     fn snapshot(&self) -> LogSnapshot {
         LogSnapshot {
             lines: self.lines.clone(),
             groups: self.groups.clone(),
+            progress: self.progress.clone(),
         }
     }
 }
@@ -84,6 +124,7 @@ impl Log for MemoryLogger {
 pub struct LogSnapshot {
     pub lines: Vec<LogLine>,
     pub groups: Vec<GroupSummary>,
+    pub progress: Vec<Progress>,
 }
 
 pub fn render_ui(snapshot: &LogSnapshot, err: Option<String>) -> io::Result<()> {
@@ -99,6 +140,21 @@ pub fn render_ui(snapshot: &LogSnapshot, err: Option<String>) -> io::Result<()>
     Ok(())
 }
 
+/// Color a unified diff line by its prefix
+fn diff_style(line: &str) -> Style {
+    if line.starts_with("+++") || line.starts_with("---") {
+        Style::default().add_modifier(Modifier::BOLD)
+    } else if line.starts_with("@@") {
+        Style::default().fg(Color::Cyan)
+    } else if line.starts_with('+') {
+        Style::default().fg(Color::Green)
+    } else if line.starts_with('-') {
+        Style::default().fg(Color::Red)
+    } else {
+        Style::default().fg(Color::DarkGray)
+    }
+}
+
 fn draw_frame(f: &mut ratatui::Frame, snapshot: &LogSnapshot, err: Option<&str>) {
     let size = f.size();
     let chunks = Layout::default()
@@ -106,6 +162,7 @@ fn draw_frame(f: &mut ratatui::Frame, snapshot: &LogSnapshot, err: Option<&str>)
         .constraints([
             Constraint::Length(3),
             Constraint::Min(10),
+            Constraint::Length(snapshot.progress.len() as u16),
             Constraint::Length(1),
         ])
         .split(size);
@@ -132,6 +189,7 @@ fn draw_frame(f: &mut ratatui::Frame, snapshot: &LogSnapshot, err: Option<&str>)
                 LogLevel::Info => Style::default(),
                 LogLevel::Ok => Style::default().fg(Color::Green),
                 LogLevel::Warn => Style::default().fg(Color::Yellow),
+                LogLevel::Diff => diff_style(&l.msg),
             };
             let mut spans: Vec<Span> = vec![Span::styled(l.msg.clone(), base_style)];
             if let Some(h) = &l.highlight {
@@ -150,6 +208,27 @@ fn draw_frame(f: &mut ratatui::Frame, snapshot: &LogSnapshot, err: Option<&str>)
     let list = List::new(items).block(Block::default().title("Steps").borders(Borders::ALL));
     f.render_widget(list, chunks[1]);
 
+    // One gauge per long-running step
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Length(1); snapshot.progress.len()])
+        .split(chunks[2]);
+    for (progress, row) in snapshot.progress.iter().zip(rows.iter()) {
+        let ratio = progress.done as f64 / progress.total.max(1) as f64;
+        let gauge = LineGauge::default()
+            .ratio(ratio.min(1.0))
+            .label(format!(
+                "{} {}/{}",
+                progress.title, progress.done, progress.total
+            ))
+            .gauge_style(Style::default().fg(if progress.done >= progress.total {
+                Color::Green
+            } else {
+                Color::Yellow
+            }));
+        f.render_widget(gauge, *row);
+    }
+
     // Summary
     let footer_text = if let Some(e) = err {
         format!("Error: {}", e)
@@ -165,5 +244,5 @@ fn draw_frame(f: &mut ratatui::Frame, snapshot: &LogSnapshot, err: Option<&str>)
         }
     };
     let footer = Paragraph::new(footer_text);
-    f.render_widget(footer, chunks[2]);
+    f.render_widget(footer, chunks[3]);
 }
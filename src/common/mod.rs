@@ -1,9 +1,23 @@
+mod backup;
+mod diff;
 mod error;
+pub mod jsonc;
 mod logging;
+mod paths;
 mod platform;
+mod review;
+mod safe_write;
+#[cfg(test)]
+mod testing;
 mod utils;
 
+pub use backup::{Backup, apply_with_rollback};
 pub use error::{SetupError, SetupResult};
 pub use logging::{Log, MemoryLogger, render_ui};
+pub use paths::{ConfigDir, config_home, data_dir, resolve_config_dir};
 pub use platform::Platform;
-pub use utils::{replace_home_with_tilde, run_command};
+pub use review::{EditMode, approve, review_edit};
+pub use safe_write::{SymlinkPolicy, TextFormat, resolve_target, write_atomic};
+#[cfg(test)]
+pub use testing::TestDir;
+pub use utils::{expand_home, replace_home_with_tilde, run_command};
@@ -0,0 +1,126 @@
+use super::{SetupError, SetupResult};
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+};
+
+/// Environment variable that points at the config asset directory
+pub const CONFIG_DIR_ENV: &str = "MAC_SETUP_CONFIG_DIR";
+
+/// Where the config asset directory was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigDirSource {
+    Flag,
+    Environment,
+    DataHome,
+    Repository,
+}
+
+impl fmt::Display for ConfigDirSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            ConfigDirSource::Flag => "--config-dir",
+            ConfigDirSource::Environment => CONFIG_DIR_ENV,
+            ConfigDirSource::DataHome => "installed",
+            ConfigDirSource::Repository => "repository",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// The resolved config asset directory
+#[derive(Debug, Clone)]
+pub struct ConfigDir {
+    pub path: PathBuf,
+    pub source: ConfigDirSource,
+}
+
+/// Stable per-user installation directory: `$XDG_DATA_HOME/mac-setup`,
+/// falling back to `~/.local/share/mac-setup`
+pub fn data_dir() -> Option<PathBuf> {
+    env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        .map(|dir| dir.join("mac-setup"))
+}
+
+/// Base directory for user configuration: `$XDG_CONFIG_HOME`, falling back to `~/.config`
+pub fn config_home() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+}
+
+/// Resolve the config asset directory from, in order: the `--config-dir` flag,
+/// `MAC_SETUP_CONFIG_DIR`, the installed copy in the data directory, and
+/// the `config/` folder of the mac-setup checkout containing the working directory.
+/// The installed copy wins so links keep pointing at it when run from a checkout.
+pub fn resolve_config_dir(flag: Option<&Path>) -> SetupResult<ConfigDir> {
+    if let Some(path) = flag {
+        return existing(path.to_path_buf(), ConfigDirSource::Flag);
+    }
+
+    if let Some(path) = env::var_os(CONFIG_DIR_ENV).filter(|p| !p.is_empty()) {
+        return existing(PathBuf::from(path), ConfigDirSource::Environment);
+    }
+
+    if let Some(path) = data_dir().map(|dir| dir.join("config"))
+        && path.is_dir()
+    {
+        return Ok(ConfigDir {
+            path,
+            source: ConfigDirSource::DataHome,
+        });
+    }
+
+    let cwd = env::current_dir()?;
+    cwd.ancestors()
+        .find(|dir| is_repository(dir))
+        .map(|dir| ConfigDir {
+            path: dir.join("config"),
+            source: ConfigDirSource::Repository,
+        })
+        .ok_or_else(|| {
+            SetupError::ConfigDirNotFound(format!(
+                "pass --config-dir, set {}, or run `mac-setup install` from the repository",
+                CONFIG_DIR_ENV
+            ))
+        })
+}
+
+/// Whether `dir` is a mac-setup checkout: a Cargo project with `config/mac-setup.toml`
+fn is_repository(dir: &Path) -> bool {
+    dir.join("Cargo.toml").is_file() && dir.join("config/mac-setup.toml").is_file()
+}
+
+fn existing(path: PathBuf, source: ConfigDirSource) -> SetupResult<ConfigDir> {
+    if path.is_dir() {
+        Ok(ConfigDir { path, source })
+    } else {
+        Err(SetupError::ConfigDirNotFound(format!(
+            "{} (from {}) is not a directory",
+            path.display(),
+            source
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::TestDir;
+    use std::fs;
+
+    #[test]
+    fn test_is_repository_needs_the_setup_config() {
+        let dir = TestDir::new("paths");
+        fs::write(dir.join("Cargo.toml"), "[package]\nname = \"other\"\n").unwrap();
+        fs::create_dir_all(dir.join("config")).unwrap();
+        assert!(!is_repository(&dir));
+
+        fs::write(dir.join("config/mac-setup.toml"), "").unwrap();
+        assert!(is_repository(&dir));
+    }
+}
@@ -0,0 +1,97 @@
+use super::{Log, diff, replace_home_with_tilde};
+use crossterm::style::Stylize;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+/// How configurators carry out edits to text files
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EditMode {
+    /// Write every change
+    #[default]
+    Apply,
+    /// Show the diffs without writing anything
+    Plan,
+    /// Ask before writing each change
+    Interactive,
+}
+
+/// Show the diff for replacing `old` with `new` at `path` and decide whether
+/// the change should be written. Unchanged content is never written.
+pub fn review_edit(
+    logger: &mut dyn Log,
+    path: &Path,
+    old: &str,
+    new: &str,
+    mode: EditMode,
+) -> bool {
+    let display = replace_home_with_tilde(path);
+    let lines = diff::unified(old, new, &display, &format!("{} (new)", display));
+    if lines.is_empty() {
+        return false;
+    }
+    logger.diff(&lines);
+
+    match mode {
+        EditMode::Apply => true,
+        EditMode::Plan => {
+            logger.info(&format!("Plan only, {} was not changed", display));
+            false
+        }
+        EditMode::Interactive => {
+            let approved = confirm(&lines, &display).unwrap_or(false);
+            if !approved {
+                logger.warn(&format!("Rejected changes to {}", display));
+            }
+            approved
+        }
+    }
+}
+
+/// Print the diff to the terminal and ask whether to apply it
+fn confirm(lines: &[String], display: &str) -> io::Result<bool> {
+    let mut stdout = io::stdout().lock();
+    for line in lines {
+        let styled = if line.starts_with("+++") || line.starts_with("---") {
+            line.as_str().bold()
+        } else if line.starts_with("@@") {
+            line.as_str().cyan()
+        } else if line.starts_with('+') {
+            line.as_str().green()
+        } else if line.starts_with('-') {
+            line.as_str().red()
+        } else {
+            line.as_str().dark_grey()
+        };
+        writeln!(stdout, "{}", styled)?;
+    }
+    drop(stdout);
+    ask(&format!("Apply changes to {}?", display))
+}
+
+/// Decide whether a change that is not a file edit, such as installing a
+/// package, may be carried out: always in apply mode, never in plan mode,
+/// and after asking in interactive mode. Plans are reported by the caller.
+pub fn approve(logger: &mut dyn Log, action: &str, mode: EditMode) -> bool {
+    match mode {
+        EditMode::Apply => true,
+        EditMode::Plan => false,
+        EditMode::Interactive => {
+            let approved = ask(&format!("{}?", action)).unwrap_or(false);
+            if !approved {
+                logger.warn(&format!("Skipped: {}", action));
+            }
+            approved
+        }
+    }
+}
+
+/// Ask a yes/no question on the terminal, defaulting to no
+fn ask(question: &str) -> io::Result<bool> {
+    let mut stdout = io::stdout().lock();
+    write!(stdout, "{} [y/N] ", question)?;
+    stdout.flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
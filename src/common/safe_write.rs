@@ -0,0 +1,190 @@
+use super::{Log, SetupError, SetupResult, replace_home_with_tilde};
+use serde::Deserialize;
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// What to do when a startup file such as `~/.zshrc` is a symlink, e.g. into a dotfiles repository
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    /// Edit the file the link points to
+    #[default]
+    Follow,
+    /// Leave the file alone
+    Refuse,
+    /// Replace the link with a regular file holding the edited content
+    Replace,
+}
+
+/// Line ending style of an existing file, re-applied to the content written back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextFormat {
+    crlf: bool,
+    trailing_newline: bool,
+}
+
+impl TextFormat {
+    pub fn detect(content: &str) -> Self {
+        Self {
+            crlf: content.contains("\r\n"),
+            trailing_newline: content.is_empty() || content.ends_with('\n'),
+        }
+    }
+
+    /// Convert `\n`-separated content ending in a newline to this format
+    pub fn apply(&self, content: &str) -> String {
+        let content = if self.trailing_newline {
+            content
+        } else {
+            content.strip_suffix('\n').unwrap_or(content)
+        };
+        if self.crlf {
+            content.replace("\r\n", "\n").replace('\n', "\r\n")
+        } else {
+            content.to_string()
+        }
+    }
+}
+
+/// File to edit for the startup file at `path` under `policy`, or `None` when it must be left alone
+pub fn resolve_target(
+    logger: &mut dyn Log,
+    path: &Path,
+    policy: SymlinkPolicy,
+) -> SetupResult<Option<PathBuf>> {
+    let is_symlink = fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink());
+    if !is_symlink {
+        return Ok(Some(path.to_path_buf()));
+    }
+
+    let display = replace_home_with_tilde(path);
+    match policy {
+        SymlinkPolicy::Follow => {
+            let target = fs::canonicalize(path).map_err(|e| {
+                SetupError::Validation(format!("{} is a broken symlink ({})", display, e))
+            })?;
+            logger.info(&format!("{} is a symlink, editing its target", display));
+            logger.ok_with_highlight("Editing ->", &replace_home_with_tilde(&target));
+            Ok(Some(target))
+        }
+        SymlinkPolicy::Refuse => {
+            logger.warn(&format!(
+                "{} is a symlink, leaving it alone (symlinked_rc = \"refuse\")",
+                display
+            ));
+            Ok(None)
+        }
+        SymlinkPolicy::Replace => {
+            logger.warn(&format!(
+                "{} is a symlink, replacing it with a regular file",
+                display
+            ));
+            Ok(Some(path.to_path_buf()))
+        }
+    }
+}
+
+/// Write `contents` to a temporary file next to `path` and rename it into place,
+/// so a crash never leaves a truncated file. The mode and owner of an existing
+/// regular file are kept; a symlink at `path` is replaced, not followed.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let staging = path.with_file_name(format!(".{}.mac-setup.tmp", name));
+    let existing = fs::symlink_metadata(path)
+        .ok()
+        .filter(|meta| meta.is_file());
+
+    let result = (|| {
+        // The owner and mode are set before any content is written, so the
+        // contents of a private file are never readable by others
+        let mut file = create_staging(&staging, existing.is_some())?;
+        if let Some(meta) = &existing {
+            keep_owner(&staging, meta)?;
+            fs::set_permissions(&staging, meta.permissions())?;
+        }
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&staging, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&staging);
+    }
+    result
+}
+
+/// Create a fresh staging file, readable only by the owner when `private`
+fn create_staging(staging: &Path, private: bool) -> io::Result<fs::File> {
+    // A leftover from an interrupted run may have looser permissions
+    let _ = fs::remove_file(staging);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    options.open(staging)
+}
+
+#[cfg(unix)]
+fn keep_owner(staging: &Path, original: &fs::Metadata) -> io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    let current = fs::metadata(staging)?;
+    if (current.uid(), current.gid()) == (original.uid(), original.gid()) {
+        return Ok(());
+    }
+    std::os::unix::fs::chown(staging, Some(original.uid()), Some(original.gid()))
+}
+
+#[cfg(windows)]
+fn keep_owner(_staging: &Path, _original: &fs::Metadata) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::TestDir;
+
+    #[test]
+    fn test_text_format_round_trip() {
+        let crlf = TextFormat::detect("a\r\nb");
+        assert_eq!(crlf.apply("a\nb\nc\n"), "a\r\nb\r\nc");
+        let plain = TextFormat::detect("a\nb\n");
+        assert_eq!(plain.apply("a\nb\nc\n"), "a\nb\nc\n");
+        assert_eq!(TextFormat::detect("").apply("a\n"), "a\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_keeps_mode_and_replaces_symlinks() {
+        use std::os::unix::fs::{PermissionsExt, symlink};
+
+        let dir = TestDir::new("write");
+
+        let file = dir.join("rc");
+        fs::write(&file, "old").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o600)).unwrap();
+        write_atomic(&file, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "new");
+        let mode = fs::metadata(&file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let link = dir.join("link");
+        symlink(&file, &link).unwrap();
+        write_atomic(&link, b"own").unwrap();
+        assert!(
+            !fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read_to_string(&file).unwrap(), "new");
+        assert!(!dir.join(".link.mac-setup.tmp").exists());
+    }
+}
@@ -0,0 +1,34 @@
+//! Helpers shared by the unit tests
+
+use std::{
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+/// Empty scratch directory below the system temp dir, removed when dropped
+pub struct TestDir(PathBuf);
+
+impl TestDir {
+    /// `name` keeps the directories of concurrently running tests apart
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("mac-setup-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -1,4 +1,8 @@
-use std::{env, path::Path, process::Command};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 pub fn replace_home_with_tilde(path: &Path) -> String {
     if let Some(home_dir) = env::var_os("HOME") {
@@ -10,6 +14,13 @@ pub fn replace_home_with_tilde(path: &Path) -> String {
     path.display().to_string()
 }
 
+/// Expands a leading `~/` to the user's home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    path.strip_prefix("~/")
+        .and_then(|stripped| env::var_os("HOME").map(|home| Path::new(&home).join(stripped)))
+        .unwrap_or_else(|| PathBuf::from(path))
+}
+
 pub fn run_command(program: &str, args: &[&str]) -> super::SetupResult<String> {
     let mut cmd = Command::new(program);
     for arg in args {
@@ -0,0 +1,163 @@
+use crate::common::{
+    EditMode, Log, SetupError, TextFormat, apply_with_rollback, replace_home_with_tilde,
+    resolve_target, review_edit, run_command, write_atomic,
+};
+use crate::configurators::Configurator;
+use crate::detectors::which::is_program_in_path;
+use crate::shells::{Shell, block};
+use crate::symlinks::SetupResult;
+use crate::user_config::ShellConfig;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// Line added to `.bash_profile` so login shells also read `.bashrc`
+const SOURCE_BASHRC: &str = "[[ -r ~/.bashrc ]] && . ~/.bashrc";
+
+/// Configurator rendering the shared environment into `.bashrc`
+pub struct BashConfigurator {
+    shell: ShellConfig,
+    mode: EditMode,
+}
+
+impl BashConfigurator {
+    pub fn new(shell: ShellConfig) -> Self {
+        Self {
+            shell,
+            mode: EditMode::default(),
+        }
+    }
+
+    /// Show, ask about or write edits according to `mode`
+    pub fn with_mode(mut self, mode: EditMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    fn home_file(name: &str) -> Option<PathBuf> {
+        env::var_os("HOME").map(|home| Path::new(&home).join(name))
+    }
+
+    /// Regenerate the managed block in `.bashrc`
+    fn modify_bashrc(&self, content: &str) -> String {
+        let mut lines: Vec<String> = content.lines().map(String::from).collect();
+        let end = lines.len();
+        block::MANAGED.upsert(&mut lines, &Shell::Bash.render(&self.shell), end);
+        lines.join("\n") + "\n"
+    }
+
+    /// Make login shells read `.bashrc`, unless `.bash_profile` already does
+    fn modify_bash_profile(content: &str) -> String {
+        let mut lines: Vec<String> = content.lines().map(String::from).collect();
+        let block_range = block::MANAGED.find(&lines);
+        let sources_bashrc = lines.iter().enumerate().any(|(i, line)| {
+            !block_range.as_ref().is_some_and(|r| r.contains(&i))
+                && !line.trim_start().starts_with('#')
+                && line.contains(".bashrc")
+        });
+        let body = if sources_bashrc {
+            Vec::new()
+        } else {
+            vec![SOURCE_BASHRC.to_string()]
+        };
+        let end = lines.len();
+        block::MANAGED.upsert(&mut lines, &body, end);
+        lines.join("\n") + "\n"
+    }
+
+    /// Rewrite a startup file with `edit`, keeping its line endings and
+    /// restoring it if `bash -n` rejects the result
+    fn write(
+        &self,
+        logger: &mut dyn Log,
+        path: &Path,
+        edit: impl FnOnce(&str) -> String,
+    ) -> SetupResult<()> {
+        let policy = self.shell.symlinked_rc.unwrap_or_default();
+        let Some(path) = resolve_target(logger, path, policy)? else {
+            return Ok(());
+        };
+        let path = path.as_path();
+        let content = fs::read_to_string(path).unwrap_or_default();
+        let new_content = TextFormat::detect(&content).apply(&edit(&content));
+        if !review_edit(logger, path, &content, &new_content, self.mode) {
+            return Ok(());
+        }
+        apply_with_rollback(
+            path,
+            || Ok(write_atomic(path, new_content.as_bytes())?),
+            || {
+                if !is_program_in_path("bash") {
+                    return Ok(());
+                }
+                run_command("bash", &["-n", &path.to_string_lossy()])
+                    .map(|_| ())
+                    .map_err(|e| {
+                        SetupError::Validation(format!(
+                            "bash -n rejected {} ({})",
+                            path.display(),
+                            e
+                        ))
+                    })
+            },
+        )?;
+        logger.ok_with_highlight("Configured bash ->", &replace_home_with_tilde(path));
+        Ok(())
+    }
+}
+
+impl Configurator for BashConfigurator {
+    fn name(&self) -> &'static str {
+        "Bash"
+    }
+
+    fn should_run(&self) -> bool {
+        [".bashrc", ".bash_profile"]
+            .into_iter()
+            .filter_map(Self::home_file)
+            .any(|path| path.exists())
+    }
+
+    fn configure(&self, logger: &mut dyn Log) -> SetupResult<()> {
+        let (Some(bashrc), Some(bash_profile)) =
+            (Self::home_file(".bashrc"), Self::home_file(".bash_profile"))
+        else {
+            return Ok(());
+        };
+
+        self.write(logger, &bashrc, |content| self.modify_bashrc(content))?;
+
+        if bash_profile.exists() {
+            self.write(logger, &bash_profile, Self::modify_bash_profile)?;
+        }
+        Ok(())
+    }
+
+    fn affected_files(&self) -> Vec<String> {
+        [".bashrc", ".bash_profile"]
+            .into_iter()
+            .filter_map(Self::home_file)
+            .filter(|path| path.exists())
+            .map(|path| replace_home_with_tilde(&path))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modify_bash_profile_sources_bashrc_once() {
+        let updated = BashConfigurator::modify_bash_profile("export A=1\n");
+        assert!(updated.contains(SOURCE_BASHRC));
+        assert_eq!(BashConfigurator::modify_bash_profile(&updated), updated);
+
+        let user_owned = "if [ -f ~/.bashrc ]; then . ~/.bashrc; fi\n";
+        assert_eq!(
+            BashConfigurator::modify_bash_profile(user_owned),
+            user_owned
+        );
+    }
+}
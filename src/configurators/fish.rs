@@ -0,0 +1,95 @@
+use crate::common::{
+    EditMode, Log, SetupError, apply_with_rollback, config_home, replace_home_with_tilde,
+    review_edit, run_command, write_atomic,
+};
+use crate::configurators::Configurator;
+use crate::detectors::which::is_program_in_path;
+use crate::shells::Shell;
+use crate::symlinks::SetupResult;
+use crate::user_config::ShellConfig;
+use std::{fs, path::PathBuf};
+
+const HEADER: &str = "# Generated by mac-setup, changes to this file are overwritten.
+# Put personal settings into another file in this directory.";
+
+/// Configurator rendering the shared environment into `~/.config/fish/conf.d/mac-setup.fish`
+pub struct FishConfigurator {
+    shell: ShellConfig,
+    mode: EditMode,
+}
+
+impl FishConfigurator {
+    pub fn new(shell: ShellConfig) -> Self {
+        Self {
+            shell,
+            mode: EditMode::default(),
+        }
+    }
+
+    /// Show, ask about or write edits according to `mode`
+    pub fn with_mode(mut self, mode: EditMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    fn conf_path() -> Option<PathBuf> {
+        config_home().map(|dir| dir.join("fish").join("conf.d").join("mac-setup.fish"))
+    }
+
+    /// Content of the generated file, or `None` when there is nothing to set
+    fn render(&self) -> Option<String> {
+        let lines = Shell::Fish.render(&self.shell);
+        (!lines.is_empty()).then(|| format!("{}\n\n{}\n", HEADER, lines.join("\n")))
+    }
+}
+
+impl Configurator for FishConfigurator {
+    fn name(&self) -> &'static str {
+        "Fish"
+    }
+
+    fn should_run(&self) -> bool {
+        is_program_in_path("fish") && Self::conf_path().is_some()
+    }
+
+    fn configure(&self, logger: &mut dyn Log) -> SetupResult<()> {
+        let Some(path) = Self::conf_path() else {
+            return Ok(());
+        };
+
+        let current = fs::read_to_string(&path).unwrap_or_default();
+        let Some(content) = self.render() else {
+            if path.exists() && review_edit(logger, &path, &current, "", self.mode) {
+                fs::remove_file(&path)?;
+                logger.ok_with_highlight("Removed ->", &replace_home_with_tilde(&path));
+            }
+            return Ok(());
+        };
+
+        if !review_edit(logger, &path, &current, &content, self.mode) {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        apply_with_rollback(
+            &path,
+            || Ok(write_atomic(&path, content.as_bytes())?),
+            || {
+                run_command("fish", &["--no-execute", &path.to_string_lossy()])
+                    .map(|_| ())
+                    .map_err(|e| {
+                        SetupError::Validation(format!("fish rejected {} ({})", path.display(), e))
+                    })
+            },
+        )?;
+        logger.ok_with_highlight("Configured fish ->", &replace_home_with_tilde(&path));
+        Ok(())
+    }
+
+    fn affected_files(&self) -> Vec<String> {
+        Self::conf_path()
+            .map(|path| vec![replace_home_with_tilde(&path)])
+            .unwrap_or_default()
+    }
+}
@@ -1,10 +1,24 @@
+pub mod bash;
+pub mod fish;
 pub mod vscode;
+pub mod vscode_settings;
 pub mod yazi;
+pub mod zsh_plugins;
 pub mod zshrc;
 
-use crate::{common::Log, symlinks::SetupResult};
+use crate::{
+    common::{EditMode, Log, expand_home},
+    detectors,
+    symlinks::SetupResult,
+    user_config::{ShellConfig, UserConfig},
+};
+pub use bash::BashConfigurator;
+pub use fish::FishConfigurator;
+use std::{fs, path::Path};
 pub use vscode::VscodeConfigurator;
+pub use vscode_settings::VscodeSettingsConfigurator;
 pub use yazi::YaziConfigurator;
+pub use zsh_plugins::ZshPluginsConfigurator;
 pub use zshrc::ZshrcConfigurator;
 
 pub trait Configurator {
@@ -27,19 +41,57 @@ pub trait Configurator {
     }
 }
 
-pub fn run_configurators(logger: &mut dyn Log) -> SetupResult<()> {
+/// Shell settings with env var preference lists resolved for this machine
+pub fn resolved_shell(logger: &mut dyn Log, user_config: &UserConfig) -> ShellConfig {
+    let mut shell = user_config.shell.clone();
+    for (key, value) in shell.resolve_env(detectors::is_command_available) {
+        logger.ok_with_highlight(&format!("Resolved {} ->", key), &value);
+    }
+    shell
+}
+
+pub fn run_configurators(
+    logger: &mut dyn Log,
+    user_config: &UserConfig,
+    config_dir: &Path,
+    mode: EditMode,
+) -> SetupResult<()> {
     logger.info("▶ Configuration");
-    let zshrc = ZshrcConfigurator::default();
-    let configurators: [&dyn Configurator; 3] = [&YaziConfigurator, &VscodeConfigurator, &zshrc];
+    let shell = resolved_shell(logger, user_config);
+    // Plugins are cloned before .zshrc enables them
+    let yazi = YaziConfigurator::new().with_mode(mode);
+    let vscode = VscodeConfigurator::new(user_config.vscode.clone()).with_mode(mode);
+    let vscode_settings = VscodeSettingsConfigurator::new(config_dir).with_mode(mode);
+    let zsh_plugins =
+        ZshPluginsConfigurator::new(user_config.zsh.external_plugins.clone()).with_mode(mode);
+    let zshrc = ZshrcConfigurator::new(user_config.zsh.clone(), shell.clone()).with_mode(mode);
+    let bash = BashConfigurator::new(shell.clone()).with_mode(mode);
+    let fish = FishConfigurator::new(shell).with_mode(mode);
+    let configurators: [&dyn Configurator; 7] = [
+        &yazi,
+        &vscode,
+        &vscode_settings,
+        &zsh_plugins,
+        &zshrc,
+        &bash,
+        &fish,
+    ];
     let mut affected = 0usize;
     for configurator in configurators {
-        configurator.run(logger)?;
-        let files = configurator.affected_files();
-        for file in files {
-            logger.ok_with_highlight("Configured successfully ->", &file);
+        if !configurator.should_run() {
+            logger.info(&format!("Skipping {}...", configurator.name()));
+            continue;
         }
-        if configurator.should_run() {
-            affected += 1;
+        affected += 1;
+
+        // Only files whose content changed were written; plans and declined edits leave them alone
+        let files = configurator.affected_files();
+        let before: Vec<Option<Vec<u8>>> = files.iter().map(|file| read(file)).collect();
+        configurator.configure(logger)?;
+        for (file, before) in files.iter().zip(before) {
+            if read(file) != before {
+                logger.ok_with_highlight("Configured successfully ->", file);
+            }
         }
     }
 
@@ -47,3 +99,7 @@ pub fn run_configurators(logger: &mut dyn Log) -> SetupResult<()> {
 
     Ok(())
 }
+
+fn read(file: &str) -> Option<Vec<u8>> {
+    fs::read(expand_home(file)).ok()
+}
@@ -1,28 +1,310 @@
-use crate::common::Log;
+use crate::common::{EditMode, Log, approve, run_command};
 use crate::configurators::Configurator;
-use crate::detectors::VSCodeDetector;
-use crate::detectors::app_detector::AppDetector;
+use crate::detectors::vscode::{VscodeVariant, installed_variants};
 use crate::symlinks::SetupResult;
-use std::collections::HashSet;
-
-/// Configurator to ensure some VS Code extensions are installed
-pub struct VscodeConfigurator;
-
-fn extensions() -> HashSet<String> {
-    [
-        "github.copilot-chat",
-        "ms-dotnettools.csdevkit",
-        "ms-dotnettools.csharp",
-        "ms-dotnettools.vscode-dotnet-runtime",
-        "pflannery.vscode-versionlens",
-        "rust-lang.rust-analyzer",
-        "vadimcn.vscode-lldb",
-        "felip3fdl.warm-burnout",
-        "isudox.vscode-jetbrains-keybindings",
-    ]
-    .into_iter()
-    .map(String::from)
-    .collect()
+use crate::user_config::VscodeConfig;
+use std::{collections::HashMap, thread, time::Duration};
+
+/// Extensions installed by one invocation of the command line tool
+const BATCH_SIZE: usize = 8;
+
+/// Further attempts for extensions that failed to install, e.g. on a network hiccup
+const RETRIES: u32 = 2;
+
+/// Pause before the first retry; later retries wait longer
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Extension ID with an optional pinned version, as in `publisher.name@1.2.3`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Extension {
+    id: String,
+    version: Option<String>,
+}
+
+impl Extension {
+    fn parse(spec: &str) -> Self {
+        match spec.trim().split_once('@') {
+            Some((id, version)) => Self {
+                id: id.to_string(),
+                version: Some(version.to_string()),
+            },
+            None => Self {
+                id: spec.trim().to_string(),
+                version: None,
+            },
+        }
+    }
+
+    /// Extension IDs are case-insensitive; `code --list-extensions` does not keep our casing
+    fn key(&self) -> String {
+        self.id.to_ascii_lowercase()
+    }
+
+    /// Whether the installed `current` satisfies this extension and its pin
+    fn matches(&self, current: &Extension) -> bool {
+        self.version.is_none() || self.version == current.version
+    }
+
+    fn spec(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{}@{}", self.id, version),
+            None => self.id.clone(),
+        }
+    }
+}
+
+/// Extensions installed and failed in one run
+#[derive(Debug, Default)]
+struct Outcome {
+    installed: usize,
+    failed: usize,
+}
+
+impl Outcome {
+    fn add(&mut self, other: Outcome) {
+        self.installed += other.installed;
+        self.failed += other.failed;
+    }
+}
+
+/// Extensions to install and uninstall to match the configuration
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Plan {
+    install: Vec<Extension>,
+    uninstall: Vec<String>,
+}
+
+/// Configurator to keep the configured extensions installed in every VS Code variant
+pub struct VscodeConfigurator {
+    config: VscodeConfig,
+    mode: EditMode,
+    /// Pause before retrying failed installs, growing with each attempt
+    retry_delay: Duration,
+}
+
+impl VscodeConfigurator {
+    pub fn new(config: VscodeConfig) -> Self {
+        Self {
+            config,
+            mode: EditMode::default(),
+            retry_delay: RETRY_DELAY,
+        }
+    }
+
+    /// In plan mode extensions are listed instead of installed or removed; interactive mode asks first
+    pub fn with_mode(mut self, mode: EditMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// The default profile (`None`) followed by every configured profile
+    fn profiles(&self) -> Vec<Option<&str>> {
+        let named = self.config.profiles.keys().map(|name| Some(name.as_str()));
+        std::iter::once(None).chain(named).collect()
+    }
+
+    /// Shared extensions plus those of `profile`; a profile's pin wins
+    fn wanted(&self, profile: Option<&str>) -> Vec<Extension> {
+        let own = profile
+            .and_then(|name| self.config.profiles.get(name))
+            .map(|profile| profile.extensions.as_slice())
+            .unwrap_or_default();
+        let mut wanted: Vec<Extension> = Vec::new();
+        for ext in self.config.extensions.iter().chain(own) {
+            let ext = Extension::parse(ext);
+            match wanted.iter_mut().find(|w| w.key() == ext.key()) {
+                Some(existing) => *existing = ext,
+                None => wanted.push(ext),
+            }
+        }
+        wanted
+    }
+
+    fn plan(&self, profile: Option<&str>, installed: &HashMap<String, Extension>) -> Plan {
+        let wanted = self.wanted(profile);
+
+        let install = wanted
+            .iter()
+            .filter(|ext| match installed.get(&ext.key()) {
+                None => true,
+                Some(current) => !ext.matches(current),
+            })
+            .cloned()
+            .collect();
+
+        let mut uninstall: Vec<String> = Vec::new();
+        if self.config.prune.unwrap_or(false) {
+            uninstall = installed
+                .iter()
+                .filter(|(key, _)| !wanted.iter().any(|ext| ext.key() == **key))
+                .filter(|(key, _)| !self.is_kept(key))
+                .map(|(_, ext)| ext.id.clone())
+                .collect();
+            uninstall.sort();
+        }
+        Plan { install, uninstall }
+    }
+
+    fn configure_variant(&self, logger: &mut dyn Log, variant: &VscodeVariant) -> Outcome {
+        let mut outcome = Outcome::default();
+        for profile in self.profiles() {
+            outcome.add(self.configure_profile(logger, variant, profile));
+        }
+        outcome
+    }
+
+    fn configure_profile(
+        &self,
+        logger: &mut dyn Log,
+        variant: &VscodeVariant,
+        profile: Option<&str>,
+    ) -> Outcome {
+        let cli = variant.cli;
+        let installed = installed_extensions(cli, profile).unwrap_or_default();
+        let plan = self.plan(profile, &installed);
+        let target = match profile {
+            Some(name) => format!("{} ({} profile)", variant.name, name),
+            None => variant.name.to_string(),
+        };
+
+        if self.mode == EditMode::Plan {
+            for ext in &plan.install {
+                logger.info(&format!(
+                    "Plan only, {} would be installed in {}",
+                    ext.spec(),
+                    target
+                ));
+            }
+            for id in &plan.uninstall {
+                logger.info(&format!(
+                    "Plan only, {} would be uninstalled from {}",
+                    id, target
+                ));
+            }
+            return Outcome::default();
+        }
+
+        let specs: Vec<String> = plan.install.iter().map(Extension::spec).collect();
+        let install: &[Extension] = if self.approve(logger, "Install", &specs, &target) {
+            &plan.install
+        } else {
+            &[]
+        };
+        let failed = self.install(logger, cli, profile, &target, install);
+        let uninstall: &[String] = if self.approve(logger, "Uninstall", &plan.uninstall, &target) {
+            &plan.uninstall
+        } else {
+            &[]
+        };
+        for id in uninstall {
+            let args = vec!["--uninstall-extension", id.as_str()];
+            match run_command(cli, &with_profile(args, profile)) {
+                Ok(_) => logger.ok_with_highlight("Uninstalled extension ->", id),
+                Err(e) => logger.warn(&format!("Failed to uninstall {}: {}", id, e)),
+            }
+        }
+        Outcome {
+            installed: install.len() - failed,
+            failed,
+        }
+    }
+
+    /// Whether `verb` may be applied to the extensions `ids` in `target`, asking in interactive mode
+    fn approve(&self, logger: &mut dyn Log, verb: &str, ids: &[String], target: &str) -> bool {
+        let action = format!("{} {} in {}", verb, ids.join(", "), target);
+        ids.is_empty() || approve(logger, &action, self.mode)
+    }
+
+    /// Install `extensions` several at a time, then check each one against the
+    /// installed list. Failed ones are retried one by one. Returns the number
+    /// of extensions that could not be installed.
+    fn install(
+        &self,
+        logger: &mut dyn Log,
+        cli: &str,
+        profile: Option<&str>,
+        target: &str,
+        extensions: &[Extension],
+    ) -> usize {
+        let title = format!("Extensions for {}", target);
+        let total = extensions.len();
+        let mut done = 0;
+        let mut pending: Vec<&Extension> = extensions.iter().collect();
+        for attempt in 0..=RETRIES {
+            if pending.is_empty() {
+                break;
+            }
+            let batch_size = if attempt == 0 {
+                BATCH_SIZE
+            } else {
+                logger.info(&format!(
+                    "Retrying {} extension(s) for {}",
+                    pending.len(),
+                    target
+                ));
+                thread::sleep(self.retry_delay * attempt);
+                1
+            };
+
+            let mut failed = Vec::new();
+            // `--force` applies to a whole invocation, so pinned extensions go separately
+            let (pinned, latest): (Vec<&Extension>, Vec<&Extension>) =
+                pending.iter().partition(|ext| ext.version.is_some());
+            for (group, force) in [(latest, false), (pinned, true)] {
+                for batch in group.chunks(batch_size) {
+                    let specs: Vec<String> = batch.iter().map(|ext| ext.spec()).collect();
+                    let mut args = Vec::new();
+                    for spec in &specs {
+                        args.extend(["--install-extension", spec.as_str()]);
+                    }
+                    if force {
+                        // Moves an already installed extension to the pinned version
+                        args.push("--force");
+                    }
+                    let status = run_command(cli, &with_profile(args, profile));
+
+                    // The exit status only tells that something in the batch failed
+                    let installed = installed_extensions(cli, profile);
+                    for ext in batch {
+                        let ok = match &installed {
+                            Some(installed) => installed
+                                .get(&ext.key())
+                                .is_some_and(|current| ext.matches(current)),
+                            None => status.is_ok(),
+                        };
+                        if ok {
+                            done += 1;
+                            logger.ok_with_highlight("Install extension ->", &ext.spec());
+                        } else {
+                            failed.push(*ext);
+                        }
+                    }
+                    logger.progress(&title, done, total);
+                }
+            }
+            pending = failed;
+        }
+
+        for ext in &pending {
+            logger.warn(&format!(
+                "Failed to install {} extension {} after {} attempts",
+                target,
+                ext.spec(),
+                RETRIES + 1
+            ));
+        }
+        pending.len()
+    }
+
+    fn is_kept(&self, key: &str) -> bool {
+        self.config.keep.iter().any(|pattern| {
+            let pattern = pattern.to_ascii_lowercase();
+            match pattern.strip_suffix('*') {
+                Some(prefix) => key.starts_with(prefix),
+                None => key == pattern,
+            }
+        })
+    }
 }
 
 impl Configurator for VscodeConfigurator {
@@ -31,26 +313,23 @@ impl Configurator for VscodeConfigurator {
     }
 
     fn should_run(&self) -> bool {
-        if !VSCodeDetector.is_installed() {
-            return false;
-        }
-        let installed = installed_extensions().unwrap_or_default();
-        let expected = extensions();
-        expected.difference(&installed).count() > 0
+        installed_variants().any(|detector| {
+            self.profiles().into_iter().any(|profile| {
+                let installed =
+                    installed_extensions(detector.variant.cli, profile).unwrap_or_default();
+                self.plan(profile, &installed) != Plan::default()
+            })
+        })
     }
 
     fn configure(&self, logger: &mut dyn Log) -> SetupResult<()> {
-        if !VSCodeDetector.is_installed() {
-            return Ok(());
+        let mut outcome = Outcome::default();
+        for detector in installed_variants() {
+            outcome.add(self.configure_variant(logger, detector.variant));
         }
-
-        let actual = installed_extensions().unwrap_or_default();
-        let expected = extensions();
-
-        for ext in expected.difference(&actual) {
-            logger.info(&format!("Installing VS Code extension: {}", ext));
-            crate::common::run_command("code", &["--install-extension", ext])?;
-            logger.ok_with_highlight("Install extension ->", ext);
+        logger.add_group("Extensions", outcome.installed);
+        if outcome.failed > 0 {
+            logger.add_group("Extensions failed", outcome.failed);
         }
         Ok(())
     }
@@ -61,15 +340,159 @@ impl Configurator for VscodeConfigurator {
     }
 }
 
-fn installed_extensions() -> Option<HashSet<String>> {
-    match crate::common::run_command("code", &["--list-extensions"]) {
-        Ok(stdout) => {
-            let set = stdout
-                .lines()
-                .map(|l| l.trim().to_string())
-                .collect::<HashSet<String>>();
-            Some(set)
-        }
-        _ => None,
+/// Extensions installed in `profile` of the variant with the command line
+/// tool `cli`, keyed by their lowercased ID
+fn installed_extensions(cli: &str, profile: Option<&str>) -> Option<HashMap<String, Extension>> {
+    let args = with_profile(vec!["--list-extensions", "--show-versions"], profile);
+    let stdout = run_command(cli, &args).ok()?;
+    Some(parse_installed(&stdout))
+}
+
+/// Add `--profile <name>` to `args` for a named profile
+fn with_profile<'a>(mut args: Vec<&'a str>, profile: Option<&'a str>) -> Vec<&'a str> {
+    if let Some(name) = profile {
+        args.extend(["--profile", name]);
+    }
+    args
+}
+
+fn parse_installed(stdout: &str) -> HashMap<String, Extension> {
+    stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(Extension::parse)
+        .map(|ext| (ext.key(), ext))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user_config::VscodeProfile;
+
+    fn configurator(extensions: &[&str], prune: bool, keep: &[&str]) -> VscodeConfigurator {
+        VscodeConfigurator::new(VscodeConfig {
+            extensions: extensions.iter().map(|e| e.to_string()).collect(),
+            prune: Some(prune),
+            keep: keep.iter().map(|e| e.to_string()).collect(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_plan_compares_ids_case_insensitively_and_honors_pins() {
+        let installed = parse_installed(
+            "GitHub.copilot-chat@0.20.0\nrust-lang.rust-analyzer@0.3.1\nms-python.python@2024.1.0\n",
+        );
+        let plan = configurator(
+            &[
+                "github.copilot-chat",
+                "rust-lang.rust-analyzer@0.3.2",
+                "vadimcn.vscode-lldb",
+            ],
+            false,
+            &[],
+        )
+        .plan(None, &installed);
+        let specs: Vec<String> = plan.install.iter().map(Extension::spec).collect();
+        assert_eq!(
+            specs,
+            vec!["rust-lang.rust-analyzer@0.3.2", "vadimcn.vscode-lldb"]
+        );
+        assert!(plan.uninstall.is_empty());
+    }
+
+    #[test]
+    fn test_plan_prunes_unlisted_extensions_except_kept_ones() {
+        let installed = parse_installed(
+            "github.copilot-chat@0.20.0\nms-python.python@2024.1.0\nms-vscode.remote-ssh@1.0.0\nfoo.bar@1.0.0\n",
+        );
+        let plan = configurator(&["GitHub.Copilot-Chat"], true, &["MS-VSCODE.*", "foo.bar"])
+            .plan(None, &installed);
+        assert!(plan.install.is_empty());
+        assert_eq!(plan.uninstall, vec!["ms-python.python"]);
+    }
+
+    #[test]
+    fn test_profiles_add_their_own_extensions() {
+        let mut config = configurator(&["github.copilot-chat", "a.b@1"], false, &[]);
+        config.config.profiles.insert(
+            "rust".to_string(),
+            VscodeProfile {
+                extensions: vec!["rust-lang.rust-analyzer".to_string(), "A.B@2".to_string()],
+            },
+        );
+        assert_eq!(config.profiles(), vec![None, Some("rust")]);
+        let specs = |profile| -> Vec<String> {
+            config.wanted(profile).iter().map(Extension::spec).collect()
+        };
+        assert_eq!(specs(None), vec!["github.copilot-chat", "a.b@1"]);
+        assert_eq!(
+            specs(Some("rust")),
+            vec!["github.copilot-chat", "A.B@2", "rust-lang.rust-analyzer"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_install_batches_verifies_and_retries() {
+        use crate::common::{MemoryLogger, TestDir};
+        use std::{fs, os::unix::fs::PermissionsExt};
+
+        let dir = TestDir::new("code");
+        // Fake command line tool that cannot install `bad.*` extensions
+        let cli = dir.join("code");
+        fs::write(
+            &cli,
+            r#"#!/bin/sh
+dir=$(dirname "$0")
+[ "$1" = --list-extensions ] && { cat "$dir/installed" 2>/dev/null; exit 0; }
+echo "$@" >> "$dir/calls"
+status=0
+while [ $# -gt 0 ]; do
+  if [ "$1" = --install-extension ]; then
+    case "$2" in
+      bad.*) status=1 ;;
+      *) echo "$2" >> "$dir/installed" ;;
+    esac
+    shift
+  fi
+  shift
+done
+exit $status
+"#,
+        )
+        .unwrap();
+        fs::set_permissions(&cli, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let extensions: Vec<Extension> = ["a.one", "a.two", "bad.ext", "pin.me@1.0"]
+            .iter()
+            .map(|spec| Extension::parse(spec))
+            .collect();
+        let mut vscode = configurator(&[], false, &[]);
+        vscode.retry_delay = Duration::ZERO;
+        let mut logger = MemoryLogger::default();
+        let failed = vscode.install(
+            &mut logger,
+            cli.to_str().unwrap(),
+            Some("rust"),
+            "Test",
+            &extensions,
+        );
+        assert_eq!(failed, 1);
+
+        let calls = fs::read_to_string(dir.join("calls")).unwrap();
+        let calls: Vec<&str> = calls.lines().collect();
+        assert_eq!(
+            calls,
+            vec![
+                "--install-extension a.one --install-extension a.two --install-extension bad.ext --profile rust",
+                "--install-extension pin.me@1.0 --force --profile rust",
+                "--install-extension bad.ext --profile rust",
+                "--install-extension bad.ext --profile rust",
+            ]
+        );
+        let progress = logger.snapshot().progress;
+        assert_eq!((progress[0].done, progress[0].total), (3, 4));
     }
 }
@@ -0,0 +1,423 @@
+use crate::common::{
+    EditMode, Log, SetupError, SymlinkPolicy, TextFormat, apply_with_rollback, data_dir,
+    expand_home,
+    jsonc::{self, JsonError, JsonNode, JsonValue},
+    replace_home_with_tilde, resolve_target, review_edit, write_atomic,
+};
+use crate::configurators::Configurator;
+use crate::detectors::{AppDetector, app_detector::MergedFile, vscode::installed_variants};
+use crate::symlinks::{SetupResult, health::is_build_output};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Indentation used when the user's file gives no hint
+const DEFAULT_INDENT: &str = "    ";
+
+/// Team key whose value the user had set differently
+#[derive(Debug, PartialEq, Eq)]
+struct Conflict {
+    key: String,
+    user: String,
+    team: String,
+}
+
+/// User settings with the team settings merged in
+#[derive(Debug)]
+struct Merge {
+    text: String,
+    added: Vec<String>,
+    conflicts: Vec<Conflict>,
+}
+
+/// Configurator merging the team settings, keybindings and snippets into the
+/// user files of every installed VS Code variant. Team entries are written,
+/// the user's own entries, comments and formatting are kept.
+pub struct VscodeSettingsConfigurator {
+    config_dir: PathBuf,
+    mode: EditMode,
+}
+
+impl VscodeSettingsConfigurator {
+    pub fn new(config_dir: &Path) -> Self {
+        Self {
+            config_dir: config_dir.to_path_buf(),
+            mode: EditMode::default(),
+        }
+    }
+
+    /// Show, ask about or write edits according to `mode`
+    pub fn with_mode(mut self, mode: EditMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Merge one file, returning the number of conflicting entries
+    fn merge_file(
+        &self,
+        logger: &mut dyn Log,
+        detector: &dyn AppDetector,
+        file: &MergedFile,
+    ) -> SetupResult<usize> {
+        let team = fs::read_to_string(&file.source)?;
+        let path = expand_home(&file.destination);
+        let display = replace_home_with_tilde(&path);
+
+        let links_to_team = is_team_link(&path, &file.source);
+        let target = if links_to_team {
+            logger.info(&format!(
+                "{} links to the team settings, replacing it with a merged copy",
+                display
+            ));
+            path
+        } else {
+            match resolve_target(logger, &path, SymlinkPolicy::Follow)? {
+                Some(target) => target,
+                None => return Ok(0),
+            }
+        };
+
+        let current = fs::read_to_string(&target).unwrap_or_default();
+        let merged = merge(&current, &team)
+            .map_err(|e| SetupError::Validation(format!("{} {}", display, e)))?;
+        for conflict in &merged.conflicts {
+            logger.warn(&format!(
+                "{}: {} was {}, replaced by the team value {}",
+                display, conflict.key, conflict.user, conflict.team
+            ));
+        }
+
+        let content = TextFormat::detect(&current).apply(&merged.text);
+        let replace_link = links_to_team && self.mode == EditMode::Apply;
+        if !review_edit(logger, &target, &current, &content, self.mode) && !replace_link {
+            return Ok(merged.conflicts.len());
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        apply_with_rollback(
+            &target,
+            || Ok(write_atomic(&target, content.as_bytes())?),
+            || detector.validate(),
+        )?;
+        for key in &merged.added {
+            logger.ok_with_highlight("Added setting ->", key);
+        }
+        logger.ok_with_highlight(&format!("Merged {} ->", detector.name()), &display);
+        Ok(merged.conflicts.len())
+    }
+}
+
+/// Whether `path` is a link earlier versions made to the team file: one that
+/// resolves to `source`, or points into a build output or installed `config`
+/// directory, even if it is dangling. Such a link is replaced by a merged copy.
+fn is_team_link(path: &Path, source: &Path) -> bool {
+    let Ok(target) = fs::read_link(path) else {
+        return false;
+    };
+    let installed = data_dir().is_some_and(|dir| target.starts_with(dir.join("config")));
+    let resolves_to_source = fs::canonicalize(path)
+        .is_ok_and(|resolved| fs::canonicalize(source).is_ok_and(|source| resolved == source));
+    resolves_to_source || installed || is_build_output(&target)
+}
+
+impl Configurator for VscodeSettingsConfigurator {
+    fn name(&self) -> &'static str {
+        "VSCode user files"
+    }
+
+    fn should_run(&self) -> bool {
+        installed_variants().next().is_some()
+    }
+
+    fn configure(&self, logger: &mut dyn Log) -> SetupResult<()> {
+        let mut conflicts = 0;
+        for detector in installed_variants() {
+            for file in detector.merged_files(&self.config_dir) {
+                conflicts += self.merge_file(logger, detector, &file)?;
+            }
+        }
+        logger.add_group("VSCode conflicts", conflicts);
+        Ok(())
+    }
+
+    fn affected_files(&self) -> Vec<String> {
+        installed_variants()
+            .flat_map(|detector| detector.merged_files(&self.config_dir))
+            .map(|file| file.destination)
+            .collect()
+    }
+}
+
+/// Merge `team` into `user`, editing the user's text in place. Settings and
+/// snippets are objects whose team keys win; keybindings are arrays that get
+/// the team bindings appended. A user file that is empty takes the team file as is.
+fn merge(user: &str, team: &str) -> Result<Merge, JsonError> {
+    let team_root = jsonc::parse(team)?;
+    if user.trim().is_empty() {
+        let added = match &team_root.value {
+            JsonValue::Object(members) => members.iter().map(|m| m.key.clone()).collect(),
+            JsonValue::Array(bindings) => bindings.iter().map(binding_label).collect(),
+            _ => return Err(unexpected(team, team_root.start, "an object or an array")),
+        };
+        let text = if team_root.is_empty() { user } else { team };
+        return Ok(Merge {
+            text: text.to_string(),
+            added,
+            conflicts: Vec::new(),
+        });
+    }
+
+    let user_root = jsonc::parse(user)?;
+    let mut merge = Merge {
+        text: String::new(),
+        added: Vec::new(),
+        conflicts: Vec::new(),
+    };
+    let mut edits = Vec::new();
+    let mut missing = Vec::new();
+    let (first, last) = match (&team_root.value, &user_root.value) {
+        (JsonValue::Object(team_members), JsonValue::Object(members)) => {
+            for member in team_members {
+                let team_value = &team[member.value.start..member.value.end];
+                // VS Code uses the last of repeated keys
+                match members.iter().rfind(|m| m.key == member.key) {
+                    Some(current) if current.value.same_value(&member.value) => {}
+                    Some(current) => {
+                        merge.conflicts.push(Conflict {
+                            key: member.key.clone(),
+                            user: compact(&user[current.value.start..current.value.end]),
+                            team: compact(team_value),
+                        });
+                        edits.push((current.value.start, current.value.end, team_value.into()));
+                    }
+                    None => {
+                        merge.added.push(member.key.clone());
+                        let key = team[member.start..member.value.start].trim_end();
+                        missing.push(format!("{} {}", key, team_value));
+                    }
+                }
+            }
+            (
+                members.first().map(|m| m.start),
+                members.last().map(|m| m.value.end),
+            )
+        }
+        (JsonValue::Array(bindings), JsonValue::Array(items)) => {
+            for binding in bindings {
+                if items.iter().any(|item| item.same_value(binding)) {
+                    continue;
+                }
+                // A later binding of the same key takes precedence
+                let rebinds = |item: &&JsonNode| {
+                    ["key", "when"]
+                        .iter()
+                        .all(|name| field(item, name) == field(binding, name))
+                        && field(item, "command") != field(binding, "command")
+                };
+                if let Some(current) = items.iter().rfind(rebinds) {
+                    merge.conflicts.push(Conflict {
+                        key: binding_label(binding),
+                        user: field(current, "command").unwrap_or_default().to_string(),
+                        team: field(binding, "command").unwrap_or_default().to_string(),
+                    });
+                }
+                merge.added.push(binding_label(binding));
+                missing.push(team[binding.start..binding.end].to_string());
+            }
+            (items.first().map(|i| i.start), items.last().map(|i| i.end))
+        }
+        (JsonValue::Object(_), _) => return Err(unexpected(user, user_root.start, "an object")),
+        _ => return Err(unexpected(user, user_root.start, "an array")),
+    };
+
+    if !missing.is_empty() {
+        let indent = first.map_or(DEFAULT_INDENT, |first| {
+            let line_start = user[..first].rfind('\n').map_or(0, |i| i + 1);
+            let prefix = &user[line_start..first];
+            if prefix.trim().is_empty() {
+                prefix
+            } else {
+                DEFAULT_INDENT
+            }
+        });
+        let lines: Vec<String> = missing
+            .iter()
+            .map(|entry| format!("\n{}{}", indent, entry))
+            .collect();
+        let close = user_root.end - 1;
+
+        match last {
+            Some(after) => {
+                // New entries go after the line of the last one, keeping a trailing comment there
+                let line_end = user[after..]
+                    .find('\n')
+                    .map_or(close, |i| (after + i).min(close));
+                edits.push((line_end, line_end, lines.join(",")));
+                if !user[after..].trim_start().starts_with(',') {
+                    edits.push((after, after, ",".to_string()));
+                }
+            }
+            None => {
+                let open = user_root.start + 1;
+                let text = format!("{}\n", lines.join(","));
+                if user[open..close].trim().is_empty() {
+                    edits.push((open, close, text));
+                } else {
+                    edits.push((close, close, text));
+                }
+            }
+        }
+    }
+
+    // Back to front, so earlier offsets stay valid; for equal offsets the
+    // edit pushed last ends up first
+    edits.sort_by_key(|edit: &(usize, usize, String)| std::cmp::Reverse(edit.0));
+    merge.text = user.to_string();
+    for (start, end, replacement) in edits {
+        merge.text.replace_range(start..end, &replacement);
+    }
+    Ok(merge)
+}
+
+fn unexpected(text: &str, offset: usize, expected: &str) -> JsonError {
+    let (line, column) = jsonc::line_col(text, offset);
+    JsonError {
+        line,
+        column,
+        message: format!("expected {} like the team file", expected),
+    }
+}
+
+/// String member `name` of a keybinding
+fn field<'a>(binding: &'a JsonNode, name: &str) -> Option<&'a str> {
+    let JsonValue::Object(members) = &binding.value else {
+        return None;
+    };
+    members
+        .iter()
+        .rfind(|m| m.key == name)
+        .and_then(|m| match &m.value.value {
+            JsonValue::String(value) => Some(value.as_str()),
+            _ => None,
+        })
+}
+
+/// Key of a keybinding with its `when` clause, for reports
+fn binding_label(binding: &JsonNode) -> String {
+    let key = field(binding, "key").unwrap_or("?");
+    match field(binding, "when") {
+        Some(when) => format!("{} when {}", key, when),
+        None => key.to_string(),
+    }
+}
+
+/// Source text of a value on one line, for reports
+fn compact(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{MemoryLogger, TestDir};
+
+    const TEAM: &str = "{\n\t// Team defaults\n\t\"editor.fontSize\": 16,\n\t\"files.exclude\": {\"**/target\": true},\n\t\"git.autofetch\": true,\n}\n";
+
+    #[test]
+    fn test_merge_keeps_user_keys_and_comments() {
+        let user = "{\n  // mine\n  \"editor.fontSize\": 14,\n  \"git.autofetch\": true,\n  \"window.zoomLevel\": 1 // laptop\n}\n";
+        let merged = merge(user, TEAM).unwrap();
+        assert_eq!(
+            merged.text,
+            "{\n  // mine\n  \"editor.fontSize\": 16,\n  \"git.autofetch\": true,\n  \"window.zoomLevel\": 1, // laptop\n  \"files.exclude\": {\"**/target\": true}\n}\n"
+        );
+        assert_eq!(merged.added, vec!["files.exclude"]);
+        assert_eq!(
+            merged.conflicts,
+            vec![Conflict {
+                key: "editor.fontSize".to_string(),
+                user: "14".to_string(),
+                team: "16".to_string(),
+            }]
+        );
+        assert!(jsonc::parse(&merged.text).is_ok());
+
+        // Merging again changes nothing
+        let again = merge(&merged.text, TEAM).unwrap();
+        assert_eq!(again.text, merged.text);
+        assert!(again.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_merge_into_empty_settings() {
+        assert_eq!(merge("", TEAM).unwrap().text, TEAM);
+        let merged = merge("{}", "{\"a\": 1, \"b\": [2],}").unwrap();
+        assert_eq!(merged.text, "{\n    \"a\": 1,\n    \"b\": [2]\n}");
+        let merged = merge("{\"x\": 0,}", "{\"a\": 1}").unwrap();
+        assert_eq!(merged.text, "{\"x\": 0,\n    \"a\": 1}");
+        assert!(merge("[]", TEAM).is_err());
+    }
+
+    #[test]
+    fn test_merge_appends_team_keybindings() {
+        let team = "[\n  {\"key\": \"cmd+k\", \"command\": \"team.run\"},\n  {\"key\": \"cmd+j\", \"command\": \"toggle\", \"when\": \"editorFocus\"},\n]";
+        let user = "[\n\t{\"key\": \"cmd+k\", \"command\": \"mine\"},\n\t{\"command\": \"toggle\", \"when\": \"editorFocus\", \"key\": \"cmd+j\"}\n]";
+        let merged = merge(user, team).unwrap();
+        assert_eq!(
+            merged.text,
+            "[\n\t{\"key\": \"cmd+k\", \"command\": \"mine\"},\n\t{\"command\": \"toggle\", \"when\": \"editorFocus\", \"key\": \"cmd+j\"},\n\t{\"key\": \"cmd+k\", \"command\": \"team.run\"}\n]"
+        );
+        assert_eq!(merged.added, vec!["cmd+k"]);
+        assert_eq!(merged.conflicts[0].user, "mine");
+        assert_eq!(merge(&merged.text, team).unwrap().text, merged.text);
+
+        // An empty team file does not create the user's file
+        assert_eq!(merge("", "// none\n[]").unwrap().text, "");
+    }
+
+    struct Dummy;
+
+    impl AppDetector for Dummy {
+        fn is_installed(&self) -> bool {
+            true
+        }
+        fn name(&self) -> &'static str {
+            "Dummy"
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_replaces_stale_link_into_build_output() {
+        let dir = TestDir::new("vscode-settings");
+        let source = dir.join("code.settings.json");
+        fs::write(&source, TEAM).unwrap();
+        let settings = dir.join("settings.json");
+        let stale = dir.join("target/debug/config/settings.json");
+        std::os::unix::fs::symlink(&stale, &settings).unwrap();
+
+        let file = MergedFile {
+            source,
+            destination: settings.display().to_string(),
+        };
+        let configurator = VscodeSettingsConfigurator::new(&dir).with_mode(EditMode::Apply);
+        let mut logger = MemoryLogger::default();
+        configurator.merge_file(&mut logger, &Dummy, &file).unwrap();
+
+        assert!(!fs::symlink_metadata(&settings).unwrap().is_symlink());
+        assert_eq!(fs::read_to_string(&settings).unwrap(), TEAM);
+        assert!(!stale.exists());
+
+        // A link into the user's own dotfiles is not the team file
+        let dotfiles = dir.join("dotfiles/code.settings.json");
+        fs::create_dir_all(dotfiles.parent().unwrap()).unwrap();
+        fs::write(&dotfiles, "{}").unwrap();
+        let own = dir.join("own.json");
+        std::os::unix::fs::symlink(&dotfiles, &own).unwrap();
+        assert!(!is_team_link(&own, &file.source));
+        assert!(is_team_link(&own, &dotfiles));
+    }
+}
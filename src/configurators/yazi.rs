@@ -1,19 +1,39 @@
-use crate::common::Log;
+use crate::common::{EditMode, Log, approve};
 use crate::configurators::Configurator;
 use crate::detectors::{YaziDetector, app_detector::AppDetector};
 use crate::symlinks::SetupResult;
 
+/// Theme package added to Yazi
+const PACKAGE: &str = "Chromium-3-Oxide/everforest-medium";
+
 /// Configurator for Yazi file manager
-pub struct YaziConfigurator;
+pub struct YaziConfigurator {
+    mode: EditMode,
+    /// Yazi's package manager command
+    program: String,
+}
 
 impl YaziConfigurator {
+    pub fn new() -> Self {
+        Self {
+            mode: EditMode::default(),
+            program: "ya".to_string(),
+        }
+    }
+
+    /// Packages are listed in plan mode and added after asking in interactive mode
+    pub fn with_mode(mut self, mode: EditMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
     fn is_installed(&self) -> bool {
         YaziDetector.is_installed()
     }
 
     /// Check if a Yazi package is already installed
     fn is_package_installed(&self, package_name: &str) -> bool {
-        match crate::common::run_command("ya", &["pkg", "list"]) {
+        match crate::common::run_command(&self.program, &["pkg", "list"]) {
             Ok(stdout) => stdout.contains(package_name),
             _ => false,
         }
@@ -21,21 +41,37 @@ impl YaziConfigurator {
 
     /// Configure Yazi by installing the everforest-medium theme package
     fn run_configure(&self, logger: &mut dyn Log) -> SetupResult<()> {
-        let package_name = "Chromium-3-Oxide/everforest-medium";
+        if self.mode == EditMode::Plan {
+            logger.info(&format!(
+                "Plan only, would add Yazi package {} unless it is installed",
+                PACKAGE
+            ));
+            return Ok(());
+        }
 
         // Check if the package is already installed
-        if self.is_package_installed(package_name) {
-            logger.info(&format!("Yazi package already installed: {}", package_name));
+        if self.is_package_installed(PACKAGE) {
+            logger.info(&format!("Yazi package already installed: {}", PACKAGE));
+            return Ok(());
+        }
+
+        if !approve(logger, &format!("Add Yazi package {}", PACKAGE), self.mode) {
             return Ok(());
         }
 
         // Run the command to install the everforest-medium package
-        crate::common::run_command("ya", &["pkg", "add", package_name])?;
-        logger.ok_with_highlight("Added Yazi package ->", package_name);
+        crate::common::run_command(&self.program, &["pkg", "add", PACKAGE])?;
+        logger.ok_with_highlight("Added Yazi package ->", PACKAGE);
         Ok(())
     }
 }
 
+impl Default for YaziConfigurator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Configurator for YaziConfigurator {
     fn name(&self) -> &'static str {
         "Yazi"
@@ -45,8 +81,8 @@ impl Configurator for YaziConfigurator {
         if !self.is_installed() {
             return false;
         }
-        // Only run if required package is missing
-        !self.is_package_installed("Chromium-3-Oxide/everforest-medium")
+        // Only run if required package is missing; a plan runs no commands
+        self.mode == EditMode::Plan || !self.is_package_installed(PACKAGE)
     }
     fn configure(&self, logger: &mut dyn Log) -> SetupResult<()> {
         self.run_configure(logger)
@@ -57,3 +93,31 @@ impl Configurator for YaziConfigurator {
         Vec::new()
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::common::{MemoryLogger, TestDir};
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    #[test]
+    fn test_plan_mode_runs_no_commands() {
+        let dir = TestDir::new("yazi");
+        let program = dir.join("ya");
+        let marker = dir.join("called");
+        fs::write(
+            &program,
+            format!("#!/bin/sh\necho \"$@\" >> \"{}\"\n", marker.display()),
+        )
+        .unwrap();
+        fs::set_permissions(&program, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut yazi = YaziConfigurator::new().with_mode(EditMode::Plan);
+        yazi.program = program.display().to_string();
+        let mut logger = MemoryLogger::default();
+        yazi.configure(&mut logger).unwrap();
+
+        assert!(!marker.exists());
+        assert!(logger.snapshot().lines[0].msg.contains(PACKAGE));
+    }
+}
@@ -0,0 +1,233 @@
+use crate::common::{EditMode, Log, SetupError, approve, replace_home_with_tilde, run_command};
+use crate::configurators::Configurator;
+use crate::detectors::{OhMyZsh, which::is_program_in_path};
+use crate::symlinks::SetupResult;
+use crate::user_config::ExternalPlugin;
+use std::path::Path;
+
+/// What happened to an external plugin checkout
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Cloned,
+    /// Checked out a different commit
+    Moved,
+    Unchanged,
+}
+
+/// Configurator cloning external zsh plugins into `$ZSH_CUSTOM/plugins`.
+/// Existing checkouts only follow a changed `ref`; fetching new commits
+/// happens when `update` is set.
+pub struct ZshPluginsConfigurator {
+    plugins: Vec<ExternalPlugin>,
+    update: bool,
+    mode: EditMode,
+}
+
+impl ZshPluginsConfigurator {
+    pub fn new(plugins: Vec<ExternalPlugin>) -> Self {
+        Self {
+            plugins,
+            update: false,
+            mode: EditMode::default(),
+        }
+    }
+
+    /// In plan mode plugins are listed instead of cloned; interactive mode asks first
+    pub fn with_mode(mut self, mode: EditMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Fetch every plugin from its remote before checking out the configured ref
+    pub fn updating(mut self) -> Self {
+        self.update = true;
+        self
+    }
+}
+
+impl Configurator for ZshPluginsConfigurator {
+    fn name(&self) -> &'static str {
+        "zsh plugins"
+    }
+
+    fn should_run(&self) -> bool {
+        !self.plugins.is_empty() && is_program_in_path("git")
+    }
+
+    fn configure(&self, logger: &mut dyn Log) -> SetupResult<()> {
+        let Some(omz) = OhMyZsh::locate() else {
+            logger.warn("oh-my-zsh not found, skipping external zsh plugins");
+            return Ok(());
+        };
+        let plugins_dir = omz.custom.join("plugins");
+
+        // A failing plugin is reported and left out of .zshrc by the zshrc configurator
+        for plugin in &self.plugins {
+            let dir = plugins_dir.join(&plugin.name);
+            let display = replace_home_with_tilde(&dir);
+            if self.mode == EditMode::Plan {
+                if !dir.exists() {
+                    logger.info(&format!("Plan only, {} would be cloned", plugin.url));
+                }
+                continue;
+            }
+            let action = format!("Clone {} into {}", plugin.url, display);
+            if !dir.exists() && !approve(logger, &action, self.mode) {
+                continue;
+            }
+            match sync_plugin(&dir, plugin, self.update) {
+                Ok(Outcome::Cloned) => logger.ok_with_highlight("Cloned plugin ->", &display),
+                Ok(Outcome::Moved) => logger.ok_with_highlight("Updated plugin ->", &display),
+                Ok(Outcome::Unchanged) => {
+                    logger.info(&format!("Plugin {} is up to date", plugin.name))
+                }
+                Err(e) => logger.warn(&format!("Plugin {} not synced: {}", plugin.name, e)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Clone `plugin` into `dir` or bring an existing checkout to the configured ref
+fn sync_plugin(dir: &Path, plugin: &ExternalPlugin, update: bool) -> SetupResult<Outcome> {
+    if !dir.exists() {
+        run_command(
+            "git",
+            &[
+                "clone",
+                "--quiet",
+                "--",
+                &plugin.url,
+                &dir.to_string_lossy(),
+            ],
+        )?;
+        if let Some(git_ref) = &plugin.git_ref {
+            let commit = resolve(dir, git_ref)?;
+            git(dir, &["checkout", "--quiet", "--detach", &commit])?;
+        }
+        return Ok(Outcome::Cloned);
+    }
+
+    if !dir.join(".git").exists() {
+        return Err(SetupError::Validation(format!(
+            "{} exists but is not a git checkout",
+            dir.display()
+        )));
+    }
+
+    let before = git(dir, &["rev-parse", "HEAD"])?;
+    if update {
+        git(dir, &["remote", "set-url", "--", "origin", &plugin.url])?;
+        git(dir, &["fetch", "--quiet", "--tags", "origin"])?;
+    } else if plugin.git_ref.is_none() {
+        return Ok(Outcome::Unchanged);
+    }
+
+    // An unpinned plugin follows the remote's default branch
+    let git_ref = plugin.git_ref.as_deref().unwrap_or("HEAD");
+    let commit = match resolve(dir, git_ref) {
+        Ok(commit) => commit,
+        // The pin may name a commit that was pushed after the last fetch
+        Err(_) if !update => {
+            git(dir, &["fetch", "--quiet", "--tags", "origin"])?;
+            resolve(dir, git_ref)?
+        }
+        Err(e) => return Err(e),
+    };
+    if commit == before {
+        return Ok(Outcome::Unchanged);
+    }
+    git(dir, &["checkout", "--quiet", "--detach", &commit])?;
+    Ok(Outcome::Moved)
+}
+
+/// Commit a ref points to, preferring the remote branch of that name
+fn resolve(dir: &Path, git_ref: &str) -> SetupResult<String> {
+    let remote = format!("refs/remotes/origin/{}^{{commit}}", git_ref);
+    git(dir, &["rev-parse", "--verify", "--quiet", &remote]).or_else(|_| {
+        git(
+            dir,
+            &[
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("{}^{{commit}}", git_ref),
+            ],
+        )
+    })
+}
+
+fn git(dir: &Path, args: &[&str]) -> SetupResult<String> {
+    let dir = dir.to_string_lossy();
+    let mut full = vec!["-C", dir.as_ref()];
+    full.extend_from_slice(args);
+    run_command("git", &full).map(|out| out.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::TestDir;
+    use std::fs;
+
+    fn commit(repo: &Path, message: &str) -> String {
+        fs::write(repo.join("demo.plugin.zsh"), message).unwrap();
+        git(repo, &["add", "-A"]).unwrap();
+        git(
+            repo,
+            &[
+                "-c",
+                "user.name=test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "--quiet",
+                "-m",
+                message,
+            ],
+        )
+        .unwrap();
+        git(repo, &["rev-parse", "HEAD"]).unwrap()
+    }
+
+    #[test]
+    fn test_clone_pin_and_update_from_file_mirror() {
+        if !is_program_in_path("git") {
+            return;
+        }
+        let root = TestDir::new("plugins");
+        let mirror = root.join("mirror");
+        fs::create_dir_all(&mirror).unwrap();
+        git(&mirror, &["init", "--quiet", "--initial-branch=main"]).unwrap();
+        let first = commit(&mirror, "first");
+        git(&mirror, &["tag", "v1"]).unwrap();
+        let second = commit(&mirror, "second");
+
+        let dir = root.join("plugins").join("demo");
+        let mut plugin = ExternalPlugin {
+            name: "demo".to_string(),
+            url: format!("file://{}", mirror.display()),
+            git_ref: Some("v1".to_string()),
+        };
+        assert_eq!(sync_plugin(&dir, &plugin, false).unwrap(), Outcome::Cloned);
+        assert_eq!(git(&dir, &["rev-parse", "HEAD"]).unwrap(), first);
+        assert_eq!(
+            sync_plugin(&dir, &plugin, false).unwrap(),
+            Outcome::Unchanged
+        );
+
+        // Unpinned plugins only move forward when updating
+        plugin.git_ref = None;
+        let third = commit(&mirror, "third");
+        assert_eq!(
+            sync_plugin(&dir, &plugin, false).unwrap(),
+            Outcome::Unchanged
+        );
+        assert_eq!(sync_plugin(&dir, &plugin, true).unwrap(), Outcome::Moved);
+        assert_eq!(git(&dir, &["rev-parse", "HEAD"]).unwrap(), third);
+
+        plugin.git_ref = Some(second.clone());
+        assert_eq!(sync_plugin(&dir, &plugin, false).unwrap(), Outcome::Moved);
+        assert_eq!(git(&dir, &["rev-parse", "HEAD"]).unwrap(), second);
+    }
+}
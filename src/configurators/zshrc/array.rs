@@ -0,0 +1,229 @@
+/// A zsh array assignment such as `plugins=(git z)` or `plugins+=(gh)`,
+/// possibly spanning several lines with comments between the elements
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArrayAssignment {
+    /// Whether this is an append (`+=`) rather than a plain assignment
+    pub append: bool,
+    /// Line index of the `name=(` line
+    pub start_line: usize,
+    /// Line index and byte column of the closing `)`
+    pub end_line: usize,
+    pub end_col: usize,
+    /// Element values with quotes and escapes removed
+    pub elements: Vec<String>,
+}
+
+/// Find all assignments to the array `name`, skipping commented-out lines.
+/// Assignments whose closing `)` is missing are returned as `Err` with their line.
+pub fn find_assignments(lines: &[String], name: &str) -> Vec<Result<ArrayAssignment, usize>> {
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        match parse_assignment(lines, i, name) {
+            Some(Ok(assignment)) => {
+                i = assignment.end_line + 1;
+                found.push(Ok(assignment));
+            }
+            Some(Err(line)) => {
+                found.push(Err(line));
+                i += 1;
+            }
+            None => i += 1,
+        }
+    }
+    found
+}
+
+/// Parse an assignment to `name` starting on line `line`, if that line starts one
+pub fn parse_assignment(
+    lines: &[String],
+    line: usize,
+    name: &str,
+) -> Option<Result<ArrayAssignment, usize>> {
+    let text = &lines[line];
+    let indent = text.len() - text.trim_start().len();
+    let rest = text[indent..].strip_prefix(name)?;
+    let (append, rest) = match rest.strip_prefix("+=(") {
+        Some(rest) => (true, rest),
+        None => (false, rest.strip_prefix("=(")?),
+    };
+    let open_col = text.len() - rest.len();
+
+    Some(
+        tokenize(lines, line, open_col)
+            .map(|(elements, end_line, end_col)| ArrayAssignment {
+                append,
+                start_line: line,
+                end_line,
+                end_col,
+                elements,
+            })
+            .ok_or(line),
+    )
+}
+
+/// Split the array body starting at (`line`, `col`) into words, honoring quotes,
+/// backslash escapes and `#` comments. Returns the words and the position of
+/// the closing `)`.
+fn tokenize(
+    lines: &[String],
+    mut line: usize,
+    mut col: usize,
+) -> Option<(Vec<String>, usize, usize)> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+
+    while line < lines.len() {
+        let text = &lines[line];
+        let mut chars = text[col..].char_indices().peekable();
+        while let Some((offset, c)) = chars.next() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some('"'), '\\') => {
+                    if let Some((_, escaped)) = chars.next() {
+                        word.get_or_insert_default().push(escaped);
+                    }
+                }
+                (Some(_), c) => word.get_or_insert_default().push(c),
+                (None, '\'' | '"') => {
+                    quote = Some(c);
+                    word.get_or_insert_default();
+                }
+                (None, '\\') => {
+                    if let Some((_, escaped)) = chars.next() {
+                        word.get_or_insert_default().push(escaped);
+                    }
+                }
+                (None, '#') if word.is_none() => break,
+                (None, ')') => {
+                    words.extend(word.take());
+                    return Some((words, line, col + offset));
+                }
+                (None, c) if c.is_whitespace() => words.extend(word.take()),
+                (None, c) => word.get_or_insert_default().push(c),
+            }
+        }
+        if quote.is_none() {
+            words.extend(word.take());
+        } else {
+            word.get_or_insert_default().push('\n');
+        }
+        line += 1;
+        col = 0;
+    }
+    None
+}
+
+/// Append `values` to an assignment while keeping its layout: single-line
+/// arrays grow in place, multi-line arrays get one new line per value using
+/// the indentation of the existing elements.
+pub fn append_elements(lines: &mut Vec<String>, assignment: &ArrayAssignment, values: &[&str]) {
+    if values.is_empty() {
+        return;
+    }
+
+    let close_line = &lines[assignment.end_line];
+    let before_close = &close_line[..assignment.end_col];
+    let close_on_own_line =
+        assignment.end_line != assignment.start_line && before_close.trim().is_empty();
+
+    if close_on_own_line {
+        let indent = lines[assignment.start_line + 1..assignment.end_line]
+            .iter()
+            .rev()
+            .find(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+            .map(|l| l[..l.len() - l.trim_start().len()].to_string())
+            .unwrap_or_else(|| "  ".to_string());
+        let new_lines = values.iter().map(|v| format!("{}{}", indent, v));
+        lines.splice(assignment.end_line..assignment.end_line, new_lines);
+    } else {
+        let needs_space =
+            !before_close.ends_with('(') && !before_close.ends_with(char::is_whitespace);
+        let insertion = format!("{}{}", if needs_space { " " } else { "" }, values.join(" "));
+        lines[assignment.end_line].insert_str(assignment.end_col, &insertion);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_single_line() {
+        let lines = to_lines("plugins=(git 'z' \"gh\")");
+        let assignment = parse_assignment(&lines, 0, "plugins").unwrap().unwrap();
+        assert!(!assignment.append);
+        assert_eq!(assignment.elements, vec!["git", "z", "gh"]);
+        assert_eq!((assignment.end_line, assignment.end_col), (0, 21));
+    }
+
+    #[test]
+    fn test_parse_multi_line_with_comments() {
+        let lines = to_lines(
+            "plugins=(\n  git # version control\n  # disabled: docker\n  z\n)\nexport A=1",
+        );
+        let found = find_assignments(&lines, "plugins");
+        assert_eq!(found.len(), 1);
+        let assignment = found[0].clone().unwrap();
+        assert_eq!(assignment.elements, vec!["git", "z"]);
+        assert_eq!(assignment.end_line, 4);
+    }
+
+    #[test]
+    fn test_parse_append_assignment() {
+        let lines = to_lines("plugins=(git)\nplugins+=(z)");
+        let found: Vec<_> = find_assignments(&lines, "plugins")
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(found.len(), 2);
+        assert!(found[1].append);
+        assert_eq!(found[1].elements, vec!["z"]);
+    }
+
+    #[test]
+    fn test_parse_ignores_comments_and_other_names() {
+        let lines = to_lines("# plugins=(git)\nmy_plugins=(x)\n  plugins=(git)");
+        let found = find_assignments(&lines, "plugins");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].clone().unwrap().start_line, 2);
+    }
+
+    #[test]
+    fn test_parse_unterminated_array() {
+        let lines = to_lines("plugins=(\n  git\n");
+        assert_eq!(find_assignments(&lines, "plugins"), vec![Err(0)]);
+    }
+
+    #[test]
+    fn test_append_multi_line_keeps_layout() {
+        let mut lines = to_lines("plugins=(\n    git # vcs\n)");
+        let assignment = parse_assignment(&lines, 0, "plugins").unwrap().unwrap();
+        append_elements(&mut lines, &assignment, &["z", "gh"]);
+        assert_eq!(
+            lines,
+            vec!["plugins=(", "    git # vcs", "    z", "    gh", ")"]
+        );
+    }
+
+    #[test]
+    fn test_append_close_after_last_element() {
+        let mut lines = to_lines("plugins=(git\n  docker)");
+        let assignment = parse_assignment(&lines, 0, "plugins").unwrap().unwrap();
+        append_elements(&mut lines, &assignment, &["z"]);
+        assert_eq!(lines, vec!["plugins=(git", "  docker z)"]);
+    }
+
+    #[test]
+    fn test_append_single_line_with_trailing_comment() {
+        let mut lines = to_lines("plugins=(git) # keep this");
+        let assignment = parse_assignment(&lines, 0, "plugins").unwrap().unwrap();
+        append_elements(&mut lines, &assignment, &["z"]);
+        assert_eq!(lines, vec!["plugins=(git z) # keep this"]);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::shells::{Shell, block};
+
+/// Marker written above each export by earlier versions of mac-setup
+const LEGACY_MARKER: &str = "# Added by mac-setup";
+
+/// A variable defined outside the managed block with a different value than configured
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvConflict {
+    pub key: String,
+    /// 1-based line number of the user's definition
+    pub line: usize,
+    pub user_value: String,
+    pub wanted: String,
+}
+
+/// Outcome of reconciling configured variables with the current file
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EnvChanges {
+    pub added: Vec<String>,
+    /// Keys whose managed value changed, with the previous value
+    pub updated: Vec<(String, String)>,
+    /// Previously managed keys that are no longer configured
+    pub removed: Vec<String>,
+    pub conflicts: Vec<EnvConflict>,
+}
+
+impl EnvChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.updated.is_empty()
+            && self.removed.is_empty()
+            && self.conflicts.is_empty()
+    }
+}
+
+/// Split `export KEY=VALUE` or `KEY=VALUE` into the key and the unquoted value
+pub fn parse_assignment(line: &str) -> Option<(&str, String)> {
+    let trimmed = line.trim();
+    let rest = trimmed
+        .strip_prefix("export ")
+        .unwrap_or(trimmed)
+        .trim_start();
+    let (key, value) = rest.split_once('=')?;
+    let is_name = !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    is_name.then(|| (key, unquote(value.trim())))
+}
+
+fn unquote(value: &str) -> String {
+    if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        return inner.to_string();
+    }
+    let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        return value.to_string();
+    };
+    // Undo the escapes `quote::double_quote` writes
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some(next @ ('"' | '\\' | '`' | '$'))) => {
+                out.push(next);
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Build the body of the managed block for `env_vars`.
+/// `previous` holds the export lines mac-setup managed before this run. Values
+/// differing from the configuration are updated, keys no longer configured are
+/// dropped, and keys the user defines outside the block are left to the user.
+/// Keys in `moved` are exported from another startup file and are not reported
+/// as removed.
+pub fn reconcile(
+    lines: &[String],
+    previous: &[String],
+    env_vars: &[(&str, &str)],
+    moved: &[&str],
+) -> (Vec<String>, EnvChanges) {
+    let block_range = block::MANAGED.find(lines);
+    let user_defined = |key: &str| {
+        lines.iter().enumerate().find_map(|(i, line)| {
+            if block_range.as_ref().is_some_and(|r| r.contains(&i)) || line.trim().starts_with('#')
+            {
+                return None;
+            }
+            parse_assignment(line)
+                .filter(|(k, _)| *k == key)
+                .map(|(_, value)| (i + 1, value))
+        })
+    };
+    let previous: Vec<(&str, String)> = previous
+        .iter()
+        .filter(|line| line.trim_start().starts_with("export "))
+        .filter_map(|line| parse_assignment(line))
+        .collect();
+    let previous_value = |key: &str| {
+        previous
+            .iter()
+            .rev()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.clone())
+    };
+
+    let mut body = Vec::new();
+    let mut changes = EnvChanges::default();
+    for &(key, value) in env_vars {
+        if let Some((line, user_value)) = user_defined(key) {
+            if user_value != value {
+                changes.conflicts.push(EnvConflict {
+                    key: key.to_string(),
+                    line,
+                    user_value,
+                    wanted: value.to_string(),
+                });
+            }
+            continue;
+        }
+
+        body.push(Shell::Zsh.export_line(key, value));
+        match previous_value(key) {
+            None => changes.added.push(key.to_string()),
+            Some(old) if old != value => changes.updated.push((key.to_string(), old)),
+            Some(_) => {}
+        }
+    }
+
+    for (key, _) in &previous {
+        let configured = env_vars.iter().any(|(k, _)| k == key) || moved.contains(key);
+        if !configured && !changes.removed.iter().any(|k| k == key) {
+            changes.removed.push(key.to_string());
+        }
+    }
+
+    (body, changes)
+}
+
+/// Remove `# Added by mac-setup` exports left by earlier versions, returning
+/// the removed export lines so they can be reconciled into the managed block
+pub fn take_legacy_exports(lines: &mut Vec<String>) -> Vec<String> {
+    let mut removed = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let is_legacy = lines[i].trim() == LEGACY_MARKER
+            && lines
+                .get(i + 1)
+                .is_some_and(|next| next.trim_start().starts_with("export "));
+        if !is_legacy {
+            i += 1;
+            continue;
+        }
+        removed.push(lines[i + 1].trim().to_string());
+        let start = if i > 0 && lines[i - 1].trim().is_empty() {
+            i - 1
+        } else {
+            i
+        };
+        lines.drain(start..=i + 1);
+        i = start;
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_assignment() {
+        assert_eq!(
+            parse_assignment("export EDITOR=\"code --wait\""),
+            Some(("EDITOR", "code --wait".to_string()))
+        );
+        assert_eq!(parse_assignment("  X='1'"), Some(("X", "1".to_string())));
+        assert_eq!(
+            parse_assignment(r#"export PS="a \"b\" \\ \$c""#),
+            Some(("PS", r#"a "b" \ $c"#.to_string()))
+        );
+        assert_eq!(parse_assignment("alias ll='ls -l'"), None);
+        assert_eq!(parse_assignment("echo a=b"), None);
+    }
+
+    #[test]
+    fn test_reconcile_updates_adds_and_removes() {
+        let lines = to_lines("source $ZSH/oh-my-zsh.sh");
+        let previous = vec!["export EDITOR=hx".to_string(), "export OLD=1".to_string()];
+        let (body, changes) = reconcile(&lines, &previous, &[("EDITOR", "vi"), ("NEW", "2")], &[]);
+        assert_eq!(body, vec!["export EDITOR=vi", "export NEW=2"]);
+        assert_eq!(changes.added, vec!["NEW"]);
+        assert_eq!(
+            changes.updated,
+            vec![("EDITOR".to_string(), "hx".to_string())]
+        );
+        assert_eq!(changes.removed, vec!["OLD"]);
+        assert!(changes.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_reconcile_keeps_moved_and_escaped_exports_quiet() {
+        let lines = to_lines("source $ZSH/oh-my-zsh.sh");
+        let previous = vec![
+            "export EDITOR=hx".to_string(),
+            r#"export GREETING="say \"hi\"""#.to_string(),
+        ];
+        let (body, changes) = reconcile(
+            &lines,
+            &previous,
+            &[("GREETING", r#"say "hi""#)],
+            &["EDITOR"],
+        );
+        assert_eq!(body, previous[1..]);
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_reconcile_reports_user_owned_conflict() {
+        let lines = to_lines("# export EDITOR=nano\nexport EDITOR=vim\nexport PAGER=less");
+        let (body, changes) = reconcile(&lines, &[], &[("EDITOR", "hx"), ("PAGER", "less")], &[]);
+        assert!(body.is_empty());
+        assert_eq!(
+            changes.conflicts,
+            vec![EnvConflict {
+                key: "EDITOR".to_string(),
+                line: 2,
+                user_value: "vim".to_string(),
+                wanted: "hx".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_take_legacy_exports() {
+        let mut lines = to_lines(
+            "a\n\n# Added by mac-setup\nexport EDITOR=hx\n\n# Added by mac-setup\nexport X=1\nb",
+        );
+        let removed = take_legacy_exports(&mut lines);
+        assert_eq!(removed, vec!["export EDITOR=hx", "export X=1"]);
+        assert_eq!(lines, vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,122 @@
+use std::ops::RangeInclusive;
+
+/// Line index of the line that loads oh-my-zsh (`source $ZSH/oh-my-zsh.sh`),
+/// also when guarded as in `[[ -r $ZSH/oh-my-zsh.sh ]] && source $ZSH/oh-my-zsh.sh`.
+/// `ZSH_THEME` and `plugins` are only honored when set before it.
+pub fn find_omz_source(lines: &[String]) -> Option<usize> {
+    lines.iter().position(|line| {
+        line.split("&&").any(|command| {
+            let mut words = command.split_whitespace();
+            matches!(words.next(), Some("source" | "."))
+                && words.next().is_some_and(|file| {
+                    file.trim_matches(|c| c == '"' || c == '\'')
+                        .ends_with("oh-my-zsh.sh")
+                })
+        })
+    })
+}
+
+/// Index at which settings that oh-my-zsh reads should be inserted: directly
+/// above the source line, or before the first statement if there is none
+pub fn pre_source_insert_pos(lines: &[String]) -> usize {
+    find_omz_source(lines).unwrap_or_else(|| {
+        lines
+            .iter()
+            .position(|l| !l.trim().is_empty() && !l.trim().starts_with('#'))
+            .unwrap_or(0)
+    })
+}
+
+/// Whether the given line comes after the oh-my-zsh source line
+pub fn is_after_source(lines: &[String], line: usize) -> bool {
+    find_omz_source(lines).is_some_and(|source| line > source)
+}
+
+/// Nesting of `if`/`case`/loop blocks after `line`, counting keywords that start a command
+pub fn block_depth(line: &str, depth: usize) -> usize {
+    if line.trim_start().starts_with('#') {
+        return depth;
+    }
+    line.split(';')
+        .filter_map(|command| command.split_whitespace().next())
+        .fold(depth, |depth, keyword| match keyword {
+            "if" | "case" | "for" | "while" | "until" => depth + 1,
+            "fi" | "esac" | "done" => depth.saturating_sub(1),
+            _ => depth,
+        })
+}
+
+/// Whether the given line lies inside an `if`/`case`/loop block
+pub fn is_nested(lines: &[String], line: usize) -> bool {
+    lines[..line]
+        .iter()
+        .fold(0, |depth, l| block_depth(l, depth))
+        > 0
+}
+
+/// Move the lines in `range` directly above the oh-my-zsh source line if they
+/// come after it. Lines inside a block are left alone, moving them would break
+/// the block. Returns whether the lines were moved.
+pub fn move_above_source(lines: &mut Vec<String>, range: RangeInclusive<usize>) -> bool {
+    let Some(source) = find_omz_source(lines) else {
+        return false;
+    };
+    if *range.start() <= source || is_nested(lines, *range.start()) {
+        return false;
+    }
+    let moved: Vec<String> = lines.drain(range).collect();
+    lines.splice(source..source, moved);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_find_omz_source() {
+        let lines = to_lines("# source $ZSH/oh-my-zsh.sh\nexport ZSH=~/.oh-my-zsh");
+        assert_eq!(find_omz_source(&lines), None);
+        let lines = to_lines("export ZSH=~/.oh-my-zsh\nsource $ZSH/oh-my-zsh.sh");
+        assert_eq!(find_omz_source(&lines), Some(1));
+        let lines = to_lines("export ZSH=~/.oh-my-zsh\n. \"$ZSH/oh-my-zsh.sh\"");
+        assert_eq!(find_omz_source(&lines), Some(1));
+        let lines = to_lines("source '$ZSH/oh-my-zsh.sh'");
+        assert_eq!(find_omz_source(&lines), Some(0));
+        let lines = to_lines("[[ -r $ZSH/oh-my-zsh.sh ]] && source $ZSH/oh-my-zsh.sh");
+        assert_eq!(find_omz_source(&lines), Some(0));
+    }
+
+    #[test]
+    fn test_move_above_source() {
+        let mut lines = to_lines("a\nsource $ZSH/oh-my-zsh.sh\nplugins=(\n  git\n)\nb");
+        assert!(move_above_source(&mut lines, 2..=4));
+        assert_eq!(
+            lines,
+            vec![
+                "a",
+                "plugins=(",
+                "  git",
+                ")",
+                "source $ZSH/oh-my-zsh.sh",
+                "b"
+            ]
+        );
+        assert!(!move_above_source(&mut lines, 1..=3));
+    }
+
+    #[test]
+    fn test_move_above_source_leaves_nested_lines() {
+        let mut lines = to_lines(
+            "source $ZSH/oh-my-zsh.sh\nif [[ $OSTYPE == darwin* ]]; then\n  plugins+=(macos)\nfi",
+        );
+        assert!(is_nested(&lines, 2));
+        assert!(!is_nested(&lines, 1));
+        assert!(!move_above_source(&mut lines, 2..=2));
+        assert_eq!(lines[2], "  plugins+=(macos)");
+    }
+}
@@ -0,0 +1,358 @@
+use super::{array, exports, layout};
+use crate::common::expand_home;
+use std::{collections::HashMap, ops::RangeInclusive, path::PathBuf};
+
+/// A problem found in .zshrc
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// 1-based line number
+    pub line: usize,
+    pub message: String,
+    fix: Option<Fix>,
+}
+
+/// Edit that resolves a finding without changing what the shell ends up with
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Fix {
+    /// Drop lines whose effect is overridden or repeated
+    Remove(RangeInclusive<usize>),
+    /// Move lines oh-my-zsh would ignore above its source line
+    MoveAboveSource(RangeInclusive<usize>),
+    /// Comment out a `source` of a file that does not exist, which prints an error on every start
+    CommentOut(usize),
+}
+
+impl Finding {
+    fn new(index: usize, message: String, fix: Option<Fix>) -> Self {
+        Self {
+            line: index + 1,
+            message,
+            fix,
+        }
+    }
+
+    pub fn is_fixable(&self) -> bool {
+        self.fix.is_some()
+    }
+}
+
+/// Analyse .zshrc content, returning findings ordered by line
+pub fn lint(content: &str) -> Vec<Finding> {
+    let lines: Vec<String> = content.lines().map(String::from).collect();
+    let mut findings = Vec::new();
+    duplicate_exports(&lines, &mut findings);
+    repeated_theme(&lines, &mut findings);
+    plugin_assignments(&lines, &mut findings);
+    path_entries(&lines, &mut findings);
+    dead_sources(&lines, &mut findings);
+    findings.sort_by_key(|f| f.line);
+    findings
+}
+
+/// Apply every safe fix, returning the new content and the number of fixes applied
+pub fn fix(content: &str) -> (String, usize) {
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let mut applied = 0;
+    // Fixes shift line numbers, so lint again after each one
+    while let Some(fix) = lint(&(lines.join("\n") + "\n"))
+        .into_iter()
+        .find_map(|f| f.fix)
+    {
+        match fix {
+            Fix::Remove(range) => {
+                lines.drain(range);
+            }
+            Fix::MoveAboveSource(range) => {
+                layout::move_above_source(&mut lines, range);
+            }
+            Fix::CommentOut(line) => lines[line] = format!("# {}", lines[line]),
+        }
+        applied += 1;
+    }
+    (lines.join("\n") + "\n", applied)
+}
+
+fn is_comment(line: &str) -> bool {
+    line.trim_start().starts_with('#')
+}
+
+/// Whether none of `indices` lies inside a block. Removing one of two settings
+/// is only safe when both always run.
+fn top_level(lines: &[String], indices: &[usize]) -> bool {
+    indices.iter().all(|&i| !layout::is_nested(lines, i))
+}
+
+/// Whether a setting on line `index` is read by oh-my-zsh
+fn before_source(lines: &[String], index: usize) -> bool {
+    !layout::is_after_source(lines, index)
+}
+
+/// `export NAME=...` repeated for the same name. Self-referencing values such
+/// as `PATH="$HOME/bin:$PATH"` extend the variable and are left alone.
+fn duplicate_exports(lines: &[String], findings: &mut Vec<Finding>) {
+    let mut seen: HashMap<&str, (usize, String)> = HashMap::new();
+    for (i, line) in lines.iter().enumerate() {
+        if !line.trim_start().starts_with("export ") {
+            continue;
+        }
+        let Some((key, value)) = exports::parse_assignment(line) else {
+            continue;
+        };
+        // A self-referencing export builds on the previous value, so later
+        // exports can no longer be compared with it
+        if value.contains(&format!("${}", key)) || value.contains(&format!("${{{}}}", key)) {
+            seen.remove(key);
+            continue;
+        }
+        if let Some((previous, previous_value)) = seen.get(key) {
+            let identical = *previous_value == value;
+            let message = if identical {
+                format!(
+                    "{} is exported again with the same value (line {})",
+                    key,
+                    previous + 1
+                )
+            } else {
+                format!(
+                    "{} is exported again, overriding line {}",
+                    key,
+                    previous + 1
+                )
+            };
+            let removable = identical && top_level(lines, &[*previous, i]);
+            findings.push(Finding::new(
+                i,
+                message,
+                removable.then_some(Fix::Remove(i..=i)),
+            ));
+        }
+        seen.insert(key, (i, value));
+    }
+}
+
+/// Several `ZSH_THEME=` lines, of which only the last before the source line counts
+fn repeated_theme(lines: &[String], findings: &mut Vec<Finding>) {
+    let themes: Vec<usize> = (0..lines.len())
+        .filter(|&i| !is_comment(&lines[i]))
+        .filter(|&i| exports::parse_assignment(&lines[i]).is_some_and(|(k, _)| k == "ZSH_THEME"))
+        .collect();
+    for pair in themes.windows(2) {
+        let (earlier, later) = (pair[0], pair[1]);
+        let overridden = before_source(lines, later) && top_level(lines, &[earlier, later]);
+        findings.push(Finding::new(
+            later,
+            format!("ZSH_THEME is set again (line {})", earlier + 1),
+            overridden.then_some(Fix::Remove(earlier..=earlier)),
+        ));
+    }
+}
+
+/// Several `plugins=(...)` assignments, and plugins set after oh-my-zsh was sourced
+fn plugin_assignments(lines: &[String], findings: &mut Vec<Finding>) {
+    let assignments: Vec<array::ArrayAssignment> = array::find_assignments(lines, "plugins")
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect();
+
+    for assignment in &assignments {
+        if layout::is_after_source(lines, assignment.start_line) {
+            let range = assignment.start_line..=assignment.end_line;
+            // Moving a line out of its block would break the block
+            let nested = layout::is_nested(lines, assignment.start_line);
+            findings.push(Finding::new(
+                assignment.start_line,
+                "plugins are set after oh-my-zsh is sourced and are ignored".to_string(),
+                (!nested).then_some(Fix::MoveAboveSource(range)),
+            ));
+        }
+    }
+
+    let plain: Vec<&array::ArrayAssignment> = assignments.iter().filter(|a| !a.append).collect();
+    for pair in plain.windows(2) {
+        let (earlier, later) = (pair[0], pair[1]);
+        let overridden = before_source(lines, later.start_line)
+            && top_level(lines, &[earlier.start_line, later.start_line]);
+        findings.push(Finding::new(
+            later.start_line,
+            format!(
+                "plugins=(...) replaces the list set on line {}",
+                earlier.start_line + 1
+            ),
+            overridden.then_some(Fix::Remove(earlier.start_line..=earlier.end_line)),
+        ));
+    }
+}
+
+/// PATH entries added more than once or pointing at directories that do not exist
+fn path_entries(lines: &[String], findings: &mut Vec<Finding>) {
+    let mut entries: Vec<(usize, String)> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if is_comment(line) {
+            continue;
+        }
+        if let Some(("PATH", value)) = exports::parse_assignment(line) {
+            entries.extend(
+                value
+                    .split(':')
+                    .filter(|dir| !dir.is_empty() && !matches!(*dir, "$PATH" | "${PATH}"))
+                    .map(|dir| (i, dir.to_string())),
+            );
+        }
+    }
+    for assignment in array::find_assignments(lines, "path")
+        .into_iter()
+        .filter_map(Result::ok)
+    {
+        entries.extend(
+            assignment
+                .elements
+                .into_iter()
+                .filter(|dir| !dir.starts_with("$path") && !dir.starts_with("${path"))
+                .map(|dir| (assignment.start_line, dir)),
+        );
+    }
+    entries.sort_by_key(|(line, _)| *line);
+
+    let mut seen: HashMap<PathBuf, usize> = HashMap::new();
+    for (i, dir) in entries {
+        let Some(path) = expand(&dir) else {
+            continue;
+        };
+        if let Some(first) = seen.get(&path) {
+            findings.push(Finding::new(
+                i,
+                format!("{} is already added to PATH on line {}", dir, first + 1),
+                None,
+            ));
+            continue;
+        }
+        if !path.is_dir() {
+            findings.push(Finding::new(
+                i,
+                format!("PATH entry {} does not exist", dir),
+                None,
+            ));
+        }
+        seen.insert(path, i);
+    }
+}
+
+/// Unguarded `source FILE` lines whose file does not exist
+fn dead_sources(lines: &[String], findings: &mut Vec<Finding>) {
+    let mut depth = 0usize;
+    for (i, line) in lines.iter().enumerate() {
+        if is_comment(line) {
+            continue;
+        }
+        // Lines inside `if`/`case` blocks are usually guarded by the condition
+        let nested = depth > 0;
+        depth = layout::block_depth(line, depth);
+        if nested {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        if !matches!(words.next(), Some("source" | ".")) {
+            continue;
+        }
+        // zsh resolves relative names against the directory and `$path`, so only
+        // absolute and home-relative files can be checked here
+        let Some(file) = words
+            .next()
+            .map(unquote)
+            .and_then(|f| expand(&f))
+            .filter(|f| f.is_absolute())
+        else {
+            continue;
+        };
+        if !file.exists() {
+            findings.push(Finding::new(
+                i,
+                format!("sourced file {} does not exist", file.display()),
+                Some(Fix::CommentOut(i)),
+            ));
+        }
+    }
+}
+
+fn unquote(word: &str) -> String {
+    word.trim_matches(|c| c == '"' || c == '\'').to_string()
+}
+
+/// Expand `~` and `$HOME`; `None` when other variables are involved
+fn expand(word: &str) -> Option<PathBuf> {
+    let word = ["$HOME", "${HOME}"]
+        .iter()
+        .find_map(|var| word.strip_prefix(var))
+        .map(|rest| format!("~{}", rest))
+        .unwrap_or_else(|| word.to_string());
+    (!word.contains('$')).then(|| expand_home(&word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(content: &str) -> Vec<(usize, String)> {
+        lint(content)
+            .into_iter()
+            .map(|f| (f.line, f.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_reports_repeated_settings_with_line_numbers() {
+        let content = "ZSH_THEME=\"a\"\nplugins=(git)\nexport EDITOR=hx\nZSH_THEME=\"b\"\nplugins=(z)\nsource $ZSH/oh-my-zsh.sh\nexport EDITOR=vim\nplugins+=(gh)\n";
+        assert_eq!(
+            messages(content),
+            vec![
+                (4, "ZSH_THEME is set again (line 1)".to_string()),
+                (
+                    5,
+                    "plugins=(...) replaces the list set on line 2".to_string()
+                ),
+                (7, "EDITOR is exported again, overriding line 3".to_string()),
+                (
+                    8,
+                    "plugins are set after oh-my-zsh is sourced and are ignored".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_path_and_source_problems() {
+        let content = "export PATH=\"/usr/bin:/nonexistent/bin:$PATH\"\npath=(/usr/bin $path)\nsource /nonexistent/aliases.zsh\n[[ -r /nonexistent/x ]] && source /nonexistent/x\nif [ -f /nonexistent/y ]; then\n  source /nonexistent/y\nfi\nsource nonexistent-aliases.zsh\nsource $ZSH/oh-my-zsh.sh\n";
+        assert_eq!(
+            messages(content),
+            vec![
+                (1, "PATH entry /nonexistent/bin does not exist".to_string()),
+                (2, "/usr/bin is already added to PATH on line 1".to_string()),
+                (
+                    3,
+                    "sourced file /nonexistent/aliases.zsh does not exist".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fix_keeps_settings_inside_blocks() {
+        let content = "ZSH_THEME=\"a\"\nexport A=1\nif [[ -n $SSH_TTY ]]; then\n  ZSH_THEME=\"b\"\n  export A=1\nfi\nsource $ZSH/oh-my-zsh.sh\n";
+        assert_eq!(lint(content).len(), 2);
+        assert_eq!(fix(content), (content.to_string(), 0));
+    }
+
+    #[test]
+    fn test_fix_applies_only_safe_changes() {
+        let content = "ZSH_THEME=\"a\"\nZSH_THEME=\"b\"\nsource $ZSH/oh-my-zsh.sh\nexport A=1\nexport A=1\nexport B=1\nexport B=2\nexport C=1\nexport C=2\nexport C=1\nplugins=(gh)\nsource /nonexistent/x.zsh\n";
+        let (fixed, applied) = fix(content);
+        assert_eq!(applied, 4);
+        assert_eq!(
+            fixed,
+            "ZSH_THEME=\"b\"\nplugins=(gh)\nsource $ZSH/oh-my-zsh.sh\nexport A=1\nexport B=1\nexport B=2\nexport C=1\nexport C=2\nexport C=1\n# source /nonexistent/x.zsh\n"
+        );
+        // The overriding exports need a decision from the user
+        assert_eq!(lint(&fixed).len(), 3);
+        assert_eq!(fix(&fixed).1, 0);
+    }
+}
@@ -0,0 +1,883 @@
+mod array;
+mod exports;
+mod layout;
+pub mod lint;
+
+use crate::{
+    assets,
+    common::{
+        EditMode, Log, SetupError, SymlinkPolicy, TextFormat, apply_with_rollback,
+        replace_home_with_tilde, resolve_target, review_edit, run_command, write_atomic,
+    },
+    configurators::Configurator,
+    detectors::{OhMyZsh, which::is_program_in_path},
+    shells::{Shell, block, quote},
+    symlinks::SetupResult,
+    user_config::{ShellConfig, StartupFile, ZshConfig},
+};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// Embedded asset used to bootstrap a missing .zshrc
+const TEMPLATE_ASSET: &str = "zshrc.template";
+
+/// New .zshrc content together with what changed in the managed exports
+pub struct ZshrcEdit {
+    pub content: String,
+    pub env_changes: exports::EnvChanges,
+    /// Problems left for the user to fix
+    pub warnings: Vec<String>,
+}
+
+/// Configurator for .zshrc file
+pub struct ZshrcConfigurator {
+    config: ZshConfig,
+    shell: ShellConfig,
+    mode: EditMode,
+}
+
+impl ZshrcConfigurator {
+    pub fn new(config: ZshConfig, shell: ShellConfig) -> Self {
+        Self {
+            config,
+            shell,
+            mode: EditMode::default(),
+        }
+    }
+
+    /// Show, ask about or write edits according to `mode`
+    pub fn with_mode(mut self, mode: EditMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Directory zsh reads its startup files from: `$ZDOTDIR`, or the home directory
+    pub fn zdotdir() -> SetupResult<PathBuf> {
+        env::var_os("ZDOTDIR")
+            .filter(|dir| !dir.is_empty())
+            .or_else(|| env::var_os("HOME"))
+            .map(PathBuf::from)
+            .ok_or_else(|| {
+                crate::common::SetupError::Io(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "HOME environment variable not set",
+                ))
+            })
+    }
+
+    /// Get the path to .zshrc in `$ZDOTDIR` or the user's home directory
+    fn get_zshrc_path() -> SetupResult<PathBuf> {
+        Ok(Self::zdotdir()?.join(StartupFile::Zshrc.file_name()))
+    }
+
+    /// Variables from `[shell.env]` exported from `file`
+    fn env_for(&self, file: StartupFile) -> Vec<(&str, &str)> {
+        self.shell
+            .env
+            .iter()
+            .filter(|(key, _)| self.config.env_files.get(*key).copied().unwrap_or_default() == file)
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect()
+    }
+
+    /// Whether the PATH entries are added in `file`
+    fn path_in(&self, file: StartupFile) -> bool {
+        self.config.path_file.unwrap_or_default() == file
+    }
+
+    /// Starter .zshrc with the oh-my-zsh bootstrap lines, used when none exists
+    fn template() -> String {
+        assets::find(TEMPLATE_ASSET)
+            .map(|asset| String::from_utf8_lossy(asset.contents).into_owned())
+            .unwrap_or_default()
+    }
+
+    /// Syntax-check the written file with `zsh -n` when zsh is available
+    fn validate(zshrc_path: &Path) -> SetupResult<()> {
+        if !is_program_in_path("zsh") {
+            return Ok(());
+        }
+        run_command("zsh", &["-n", &zshrc_path.to_string_lossy()])
+            .map(|_| ())
+            .map_err(|e| {
+                SetupError::Validation(format!("zsh -n rejected {} ({})", zshrc_path.display(), e))
+            })
+    }
+
+    /// Report problems in .zshrc with their line numbers, applying the safe fixes when `fix` is set
+    pub fn lint(
+        logger: &mut dyn Log,
+        fix: bool,
+        policy: SymlinkPolicy,
+        mode: EditMode,
+    ) -> SetupResult<()> {
+        let zshrc_path = Self::get_zshrc_path()?;
+        let display = replace_home_with_tilde(&zshrc_path);
+        if !zshrc_path.exists() {
+            logger.warn(&format!("No .zshrc at {}", display));
+            return Ok(());
+        }
+        let content = fs::read_to_string(&zshrc_path)?;
+
+        if fix
+            && let (fixed, applied) = lint::fix(&content)
+            && applied > 0
+            && let Some(target) = resolve_target(logger, &zshrc_path, policy)?
+            && let fixed = TextFormat::detect(&content).apply(&fixed)
+            && review_edit(logger, &target, &content, &fixed, mode)
+        {
+            apply_with_rollback(
+                &target,
+                || Ok(write_atomic(&target, fixed.as_bytes())?),
+                || Self::validate(&target),
+            )?;
+            logger.ok_with_highlight(&format!("Applied {} fixes to ->", applied), &display);
+        }
+
+        let findings = lint::lint(&fs::read_to_string(&zshrc_path)?);
+        if findings.is_empty() {
+            logger.ok_with_highlight("No problems found in ->", &display);
+        }
+        for finding in &findings {
+            let hint = if finding.is_fixable() {
+                " (fixable with --fix)"
+            } else {
+                ""
+            };
+            logger.warn(&format!(
+                "{}:{}: {}{}",
+                display, finding.line, finding.message, hint
+            ));
+        }
+        logger.add_group("Lint findings", findings.len());
+        Ok(())
+    }
+
+    /// Configure .zshrc with the specified theme, plugins, and environment variables,
+    /// then the managed blocks of .zshenv and .zprofile
+    fn run_configure(&self, logger: &mut dyn Log) -> SetupResult<()> {
+        self.configure_zshrc(logger)?;
+        for file in [StartupFile::Zshenv, StartupFile::Zprofile] {
+            self.configure_startup_file(logger, file)?;
+        }
+        Ok(())
+    }
+
+    /// Show the change to `path` and write it atomically, restoring the previous
+    /// file if zsh rejects the result. Returns whether the file was written.
+    fn write_file(
+        &self,
+        logger: &mut dyn Log,
+        path: &Path,
+        old: &str,
+        new: &str,
+    ) -> SetupResult<bool> {
+        if !review_edit(logger, path, old, new, self.mode) {
+            return Ok(false);
+        }
+        apply_with_rollback(
+            path,
+            || Ok(write_atomic(path, new.as_bytes())?),
+            || Self::validate(path),
+        )
+        .inspect_err(|e| {
+            logger.warn(&format!("Restored previous {}: {}", path.display(), e));
+        })?;
+        Ok(true)
+    }
+
+    fn configure_zshrc(&self, logger: &mut dyn Log) -> SetupResult<()> {
+        let zshrc_path = Self::get_zshrc_path()?;
+
+        logger.info(&format!("Configuring .zshrc at {:?}...", zshrc_path));
+        let policy = self.shell.symlinked_rc.unwrap_or_default();
+        let Some(zshrc_path) = resolve_target(logger, &zshrc_path, policy)? else {
+            return Ok(());
+        };
+
+        // Read the current content, starting from the template on fresh machines
+        let created = !zshrc_path.exists();
+        let content = if created {
+            Self::template()
+        } else {
+            fs::read_to_string(&zshrc_path)?
+        };
+
+        // Modify the content, leaving out themes and plugins oh-my-zsh could not load
+        let checked = Self {
+            config: self.checked_config(logger),
+            shell: self.shell.clone(),
+            mode: self.mode,
+        };
+        let edit = checked.modify_zshrc_content(&content);
+        for warning in &edit.warnings {
+            logger.warn(warning);
+        }
+        let new_content = TextFormat::detect(&content).apply(&edit.content);
+
+        let old_content = if created { "" } else { content.as_str() };
+        if !self.write_file(logger, &zshrc_path, old_content, &new_content)? {
+            if self.mode == EditMode::Apply {
+                logger.info(".zshrc is up to date");
+            }
+            return Ok(());
+        }
+
+        if created {
+            logger.ok_with_highlight(
+                "Created .zshrc from template at ->",
+                &replace_home_with_tilde(&zshrc_path),
+            );
+        } else {
+            logger.ok_with_highlight(
+                "Configured .zshrc at ->",
+                &replace_home_with_tilde(&zshrc_path),
+            );
+        }
+
+        logger.info(".zshrc configured successfully");
+        if let Some(theme) = &checked.config.theme {
+            logger.info(&format!("  - Theme set to: {}", theme));
+        }
+        logger.info(&format!(
+            "  - Plugins: {}",
+            checked.config.plugins.join(", ")
+        ));
+        log_env_changes(logger, &edit.env_changes);
+
+        Ok(())
+    }
+
+    /// Write the settings routed to `.zshenv` or `.zprofile` into its managed block
+    fn configure_startup_file(&self, logger: &mut dyn Log, file: StartupFile) -> SetupResult<()> {
+        let path = Self::zdotdir()?.join(file.file_name());
+        let wanted = self.path_in(file) || !self.env_for(file).is_empty();
+        if !wanted && !path.exists() {
+            return Ok(());
+        }
+        let policy = self.shell.symlinked_rc.unwrap_or_default();
+        let Some(path) = resolve_target(logger, &path, policy)? else {
+            return Ok(());
+        };
+
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        let new_content =
+            TextFormat::detect(&content).apply(&self.modify_startup_content(file, &content));
+        if self.write_file(logger, &path, &content, &new_content)? {
+            logger.ok_with_highlight(
+                &format!("Configured {} at ->", file.file_name()),
+                &replace_home_with_tilde(&path),
+            );
+        }
+        Ok(())
+    }
+
+    /// Configuration with the theme and plugins that oh-my-zsh cannot find removed.
+    /// A missing theme keeps whatever theme .zshrc already selects.
+    fn checked_config(&self, logger: &mut dyn Log) -> ZshConfig {
+        let mut config = self.config.clone();
+        let Some(omz) = OhMyZsh::locate() else {
+            logger.warn("oh-my-zsh not found, skipping theme and plugin checks");
+            return config;
+        };
+        // External plugins were cloned into $ZSH_CUSTOM by the zsh plugins configurator
+        config.plugins = config.plugin_names();
+
+        if let Some(theme) = &config.theme
+            && omz.theme_file(theme).is_none()
+        {
+            logger.warn(&format!(
+                "Theme '{}' not found in {} or {}, keeping current theme",
+                theme,
+                replace_home_with_tilde(&omz.root.join("themes")),
+                replace_home_with_tilde(&omz.custom),
+            ));
+            config.theme = None;
+        }
+
+        config.plugins.retain(|plugin| {
+            let found = omz.has_plugin(plugin);
+            if !found {
+                logger.warn(&format!("Plugin '{}' not found, skipping it", plugin));
+            }
+            found
+        });
+
+        config
+    }
+
+    /// Modify the .zshrc content by updating theme and plugins, and regenerating the managed blocks
+    fn modify_zshrc_content(&self, content: &str) -> ZshrcEdit {
+        let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+
+        // Update or add ZSH_THEME
+        if let Some(theme) = &self.config.theme {
+            self.update_or_add_line(&mut lines, "ZSH_THEME", &format!("ZSH_THEME=\"{}\"", theme));
+        }
+
+        // Extend plugins instead of replacing them
+        let plugins: Vec<&str> = self.config.plugins.iter().map(String::as_str).collect();
+        let warnings = self.extend_plugins(&mut lines, &plugins);
+
+        // fpath has to be extended before oh-my-zsh runs compinit
+        let before_omz = self.before_omz_lines();
+        let pos = layout::pre_source_insert_pos(&lines);
+        block::BEFORE_OMZ.upsert(&mut lines, &before_omz, pos);
+
+        // Everything else lives in the managed block, which is regenerated on every
+        // run and belongs after oh-my-zsh is sourced so it can override its defaults
+        let mut previous = exports::take_legacy_exports(&mut lines);
+        previous.extend(block::MANAGED.body(&lines));
+        if let (Some(range), Some(source)) =
+            (block::MANAGED.find(&lines), layout::find_omz_source(&lines))
+            && *range.start() < source
+        {
+            block::MANAGED.remove(&mut lines);
+        }
+        let env_vars = self.env_for(StartupFile::Zshrc);
+        let moved: Vec<&str> = [StartupFile::Zshenv, StartupFile::Zprofile]
+            .into_iter()
+            .flat_map(|file| self.env_for(file))
+            .map(|(key, _)| key)
+            .collect();
+        let (exports, env_changes) = exports::reconcile(&lines, &previous, &env_vars, &moved);
+        let body = self.managed_lines(exports);
+        let end = lines.len();
+        block::MANAGED.upsert(&mut lines, &body, end);
+
+        ZshrcEdit {
+            content: lines.join("\n") + "\n",
+            env_changes,
+            warnings,
+        }
+    }
+
+    /// Regenerate the managed block of `.zshenv` or `.zprofile`
+    fn modify_startup_content(&self, file: StartupFile, content: &str) -> String {
+        let mut lines: Vec<String> = content.lines().map(String::from).collect();
+        let mut body = Vec::new();
+        if self.path_in(file) {
+            body = Shell::Zsh.path_lines(&self.shell.path_prepend, &self.shell.path_append);
+        }
+        for (key, value) in self.env_for(file) {
+            body.push(Shell::Zsh.export_line(key, value));
+        }
+        let end = lines.len();
+        block::MANAGED.upsert(&mut lines, &body, end);
+        lines.join("\n") + "\n"
+    }
+
+    /// Lines of the block placed before oh-my-zsh is sourced
+    fn before_omz_lines(&self) -> Vec<String> {
+        if self.config.fpath.is_empty() {
+            return Vec::new();
+        }
+        let dirs: Vec<String> = self.config.fpath.iter().map(|d| quote::path(d)).collect();
+        vec![format!("fpath=({} $fpath)", dirs.join(" "))]
+    }
+
+    /// Lines of the managed block: PATH entries, exports, aliases and sourced files
+    fn managed_lines(&self, exports: Vec<String>) -> Vec<String> {
+        let shell = &self.shell;
+        let mut body = Vec::new();
+        if self.path_in(StartupFile::Zshrc) {
+            body = Shell::Zsh.path_lines(&shell.path_prepend, &shell.path_append);
+        }
+
+        body.extend(exports);
+
+        for (name, command) in &shell.aliases {
+            body.push(Shell::Zsh.alias_line(name, command));
+        }
+
+        for file in &self.config.source {
+            let file = quote::path(file);
+            body.push(format!("[[ -r {} ]] && source {}", file, file));
+        }
+
+        body
+    }
+
+    /// Extend the plugins list with new plugins (avoiding duplicates).
+    /// Returns warnings about assignments that could not be fixed.
+    fn extend_plugins(&self, lines: &mut Vec<String>, plugins_to_add: &[&str]) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        // oh-my-zsh ignores plugins set after it is sourced; only top-level lines can be moved
+        loop {
+            let misplaced: Vec<array::ArrayAssignment> = array::find_assignments(lines, "plugins")
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|a| layout::is_after_source(lines, a.start_line))
+                .collect();
+            match misplaced
+                .iter()
+                .find(|a| !layout::is_nested(lines, a.start_line))
+            {
+                Some(a) => {
+                    layout::move_above_source(lines, a.start_line..=a.end_line);
+                }
+                None => {
+                    warnings.extend(misplaced.iter().map(|a| {
+                        format!(
+                            "plugins set inside a block after oh-my-zsh is sourced are ignored: {}",
+                            lines[a.start_line].trim()
+                        )
+                    }));
+                    break;
+                }
+            }
+        }
+
+        let mut assignments = Vec::new();
+        for found in array::find_assignments(lines, "plugins") {
+            match found {
+                Ok(assignment) => assignments.push(assignment),
+                // An unterminated array cannot be edited safely, leave it to the user
+                Err(_) => return warnings,
+            }
+        }
+        // Conditional assignments may not run, so only the top-level ones are extended
+        let (nested, top_level): (Vec<_>, Vec<_>) = assignments
+            .into_iter()
+            .partition(|a| layout::is_nested(lines, a.start_line));
+
+        // Evaluate the assignments in order: `=` resets the list, `+=` extends it
+        let mut plugins: Vec<&str> = Vec::new();
+        for assignment in &top_level {
+            if !assignment.append {
+                plugins.clear();
+            }
+            plugins.extend(assignment.elements.iter().map(String::as_str));
+        }
+
+        let mut missing: Vec<&str> = Vec::new();
+        for &p in plugins_to_add {
+            if !plugins.contains(&p) && !missing.contains(&p) {
+                missing.push(p);
+            }
+        }
+
+        match top_level.iter().rev().find(|a| !a.append) {
+            Some(assignment) => array::append_elements(lines, assignment, &missing),
+            None if !missing.is_empty() => {
+                // Keep the plugins a nested assignment may have set
+                let operator = if nested.is_empty() { "=" } else { "+=" };
+                let insert_pos = layout::pre_source_insert_pos(lines);
+                lines.insert(
+                    insert_pos,
+                    format!("plugins{}({})", operator, missing.join(" ")),
+                );
+            }
+            None => {}
+        }
+        warnings
+    }
+
+    /// Update an existing line or add a new one above the oh-my-zsh source line.
+    /// An existing line below the source line is moved above it.
+    fn update_or_add_line(&self, lines: &mut Vec<String>, key: &str, new_line: &str) {
+        if let Some(pos) = lines
+            .iter()
+            .position(|l| exports::parse_assignment(l).is_some_and(|(k, _)| k == key))
+        {
+            lines[pos] = new_line.to_string();
+            layout::move_above_source(lines, pos..=pos);
+        } else {
+            let insert_pos = layout::pre_source_insert_pos(lines);
+            lines.insert(insert_pos, new_line.to_string());
+        }
+    }
+}
+
+/// Report how the managed exports were reconciled
+fn log_env_changes(logger: &mut dyn Log, changes: &exports::EnvChanges) {
+    if changes.is_empty() {
+        logger.info("  - Exports up to date");
+    }
+    for key in &changes.added {
+        logger.ok_with_highlight("  - Added export ->", key);
+    }
+    for (key, old) in &changes.updated {
+        logger.ok_with_highlight(&format!("  - Updated export (was {}) ->", old), key);
+    }
+    for key in &changes.removed {
+        logger.ok_with_highlight("  - Removed export ->", key);
+    }
+    for conflict in &changes.conflicts {
+        logger.warn(&format!(
+            "  - {} is set to '{}' on line {} outside the managed block, keeping it instead of '{}'",
+            conflict.key, conflict.user_value, conflict.line, conflict.wanted
+        ));
+    }
+}
+
+impl Configurator for ZshrcConfigurator {
+    fn name(&self) -> &'static str {
+        "ZSH"
+    }
+
+    fn should_run(&self) -> bool {
+        // A missing .zshrc is created from the template
+        Self::get_zshrc_path().is_ok()
+    }
+
+    fn configure(&self, logger: &mut dyn Log) -> SetupResult<()> {
+        self.run_configure(logger)
+    }
+
+    fn affected_files(&self) -> Vec<String> {
+        let Ok(dir) = Self::zdotdir() else {
+            return Vec::new();
+        };
+        [
+            StartupFile::Zshrc,
+            StartupFile::Zshenv,
+            StartupFile::Zprofile,
+        ]
+        .into_iter()
+        .map(|file| dir.join(file.file_name()))
+        .filter(|path| path.exists())
+        .map(|path| replace_home_with_tilde(&path))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user_config::EnvValue;
+
+    fn new_zsh_configurator() -> ZshrcConfigurator {
+        ZshrcConfigurator::new(ZshConfig::default(), ShellConfig::default())
+    }
+
+    fn configurator_with(theme: &str, plugins: &[&str], env: &[(&str, &str)]) -> ZshrcConfigurator {
+        ZshrcConfigurator::new(
+            ZshConfig {
+                theme: Some(theme.to_string()),
+                plugins: plugins.iter().map(|p| p.to_string()).collect(),
+                ..ZshConfig::default()
+            },
+            ShellConfig {
+                env: env
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), EnvValue::Value(v.to_string())))
+                    .collect(),
+                ..ShellConfig::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_extend_plugins_add_to_existing() {
+        let configurator = new_zsh_configurator();
+        let mut lines = vec!["plugins=(git zsh-syntax-highlighting)".to_string()];
+        configurator.extend_plugins(&mut lines, &["z", "gh"]);
+        assert_eq!(lines, vec!["plugins=(git zsh-syntax-highlighting z gh)"]);
+    }
+
+    #[test]
+    fn test_extend_plugins_add_with_duplicates() {
+        let configurator = new_zsh_configurator();
+        let mut lines = vec!["plugins=(git z)".to_string()];
+        configurator.extend_plugins(&mut lines, &["z", "gh"]);
+        assert_eq!(lines, vec!["plugins=(git z gh)"]);
+    }
+
+    #[test]
+    fn test_extend_plugins_no_existing_plugins_line() {
+        let configurator = new_zsh_configurator();
+        let mut lines = vec!["some line".to_string()];
+        configurator.extend_plugins(&mut lines, &["z", "gh"]);
+        assert_eq!(lines, vec!["plugins=(z gh)", "some line"]);
+    }
+
+    #[test]
+    fn test_extend_plugins_empty_initial_plugins() {
+        let configurator = new_zsh_configurator();
+        let mut lines = vec!["plugins=()".to_string()];
+        configurator.extend_plugins(&mut lines, &["z", "gh"]);
+        assert_eq!(lines, vec!["plugins=(z gh)"]);
+    }
+
+    #[test]
+    fn test_update_or_add_line_updates_existing() {
+        let configurator = new_zsh_configurator();
+        let mut lines = vec!["ZSH_THEME=\"old_theme\"".to_string()];
+        configurator.update_or_add_line(&mut lines, "ZSH_THEME", "ZSH_THEME=\"new_theme\"");
+        assert_eq!(lines, vec!["ZSH_THEME=\"new_theme\""]);
+    }
+
+    #[test]
+    fn test_update_or_add_line_adds_new() {
+        let configurator = new_zsh_configurator();
+        let mut lines = vec!["some_other_line=\"value\"".to_string()];
+        configurator.update_or_add_line(&mut lines, "ZSH_THEME", "ZSH_THEME=\"new_theme\"");
+        assert_eq!(
+            lines,
+            vec!["ZSH_THEME=\"new_theme\"", "some_other_line=\"value\""]
+        );
+    }
+
+    #[test]
+    fn test_update_or_add_line_ignores_commented_line() {
+        let configurator = new_zsh_configurator();
+        let mut lines = vec![
+            "# ZSH_THEME=\"old_theme\"".to_string(),
+            "other_line=\"value\"".to_string(),
+        ];
+        configurator.update_or_add_line(&mut lines, "ZSH_THEME", "ZSH_THEME=\"new_theme\"");
+        assert_eq!(
+            lines,
+            vec![
+                "# ZSH_THEME=\"old_theme\"",
+                "ZSH_THEME=\"new_theme\"",
+                "other_line=\"value\""
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_zshrc_content_moves_legacy_exports_into_block() {
+        let content = "plugins=(git)\n\n# Added by mac-setup\nexport EDITOR=hx\n";
+        let result = configurator_with("stefc", &[], &[("EDITOR", "hx")])
+            .modify_zshrc_content(content)
+            .content;
+        assert_eq!(
+            result,
+            format!(
+                "ZSH_THEME=\"stefc\"\nplugins=(git)\n\n{}\n{}\nexport EDITOR=hx\n{}\n",
+                block::MANAGED.begin,
+                "# Managed by mac-setup, changes inside this block are overwritten",
+                block::MANAGED.end
+            )
+        );
+        // Running again is a no-op
+        let again =
+            configurator_with("stefc", &[], &[("EDITOR", "hx")]).modify_zshrc_content(&result);
+        assert_eq!(again.content, result);
+        assert!(again.env_changes.is_empty());
+    }
+
+    #[test]
+    fn test_extend_plugins_multi_line_array() {
+        let configurator = new_zsh_configurator();
+        let mut lines: Vec<String> = ["plugins=(", "  git  # vcs", "  'docker'", ")"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        configurator.extend_plugins(&mut lines, &["docker", "z"]);
+        assert_eq!(
+            lines,
+            vec!["plugins=(", "  git  # vcs", "  'docker'", "  z", ")"]
+        );
+    }
+
+    #[test]
+    fn test_extend_plugins_respects_append_assignment() {
+        let configurator = new_zsh_configurator();
+        let mut lines = vec!["plugins=(git)".to_string(), "plugins+=(z)".to_string()];
+        configurator.extend_plugins(&mut lines, &["z", "gh"]);
+        assert_eq!(lines, vec!["plugins=(git gh)", "plugins+=(z)"]);
+    }
+
+    #[test]
+    fn test_extend_plugins_leaves_nested_assignments_in_place() {
+        let configurator = new_zsh_configurator();
+        let mut lines: Vec<String> = [
+            "source $ZSH/oh-my-zsh.sh",
+            "if [[ $OSTYPE == darwin* ]]; then",
+            "  plugins=(macos)",
+            "fi",
+        ]
+        .map(String::from)
+        .to_vec();
+        let warnings = configurator.extend_plugins(&mut lines, &["z"]);
+        assert_eq!(
+            lines,
+            vec![
+                "plugins+=(z)",
+                "source $ZSH/oh-my-zsh.sh",
+                "if [[ $OSTYPE == darwin* ]]; then",
+                "  plugins=(macos)",
+                "fi"
+            ]
+        );
+        assert_eq!(
+            warnings,
+            vec![
+                "plugins set inside a block after oh-my-zsh is sourced are ignored: plugins=(macos)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_update_or_add_line_matches_exact_key() {
+        let configurator = new_zsh_configurator();
+        let mut lines = vec!["ZSH_THEME_GIT_PROMPT_PREFIX=\"(\"".to_string()];
+        configurator.update_or_add_line(&mut lines, "ZSH_THEME", "ZSH_THEME=\"new_theme\"");
+        assert_eq!(
+            lines,
+            vec![
+                "ZSH_THEME=\"new_theme\"",
+                "ZSH_THEME_GIT_PROMPT_PREFIX=\"(\""
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extend_plugins_leaves_unterminated_array_alone() {
+        let configurator = new_zsh_configurator();
+        let mut lines = vec!["plugins=(".to_string(), "  git".to_string()];
+        configurator.extend_plugins(&mut lines, &["z"]);
+        assert_eq!(lines, vec!["plugins=(", "  git"]);
+    }
+
+    #[test]
+    fn test_modify_zshrc_content_places_settings_around_omz_source() {
+        let content = [
+            "export ZSH=\"$HOME/.oh-my-zsh\"",
+            "source $ZSH/oh-my-zsh.sh",
+            "ZSH_THEME=\"robbyrussell\"",
+            "plugins=(",
+            "  git",
+            ")",
+            "alias ll='ls -l'",
+        ]
+        .join("\n");
+        let result = configurator_with("stefc", &["z"], &[("EDITOR", "hx")])
+            .modify_zshrc_content(&content)
+            .content;
+        let lines: Vec<&str> = result.lines().collect();
+        assert_eq!(
+            lines[..7],
+            [
+                "export ZSH=\"$HOME/.oh-my-zsh\"",
+                "ZSH_THEME=\"stefc\"",
+                "plugins=(",
+                "  git",
+                "  z",
+                ")",
+                "source $ZSH/oh-my-zsh.sh",
+            ]
+        );
+        assert_eq!(lines[7], "alias ll='ls -l'");
+        assert!(lines[8..].contains(&"export EDITOR=hx"));
+    }
+
+    #[test]
+    fn test_modify_zshrc_content_moves_block_after_omz_source() {
+        let content = format!(
+            "{}\nexport EDITOR=hx\n{}\nplugins=(git)\nsource $ZSH/oh-my-zsh.sh",
+            block::MANAGED.begin,
+            block::MANAGED.end
+        );
+        let result = configurator_with("stefc", &[], &[("EDITOR", "hx")])
+            .modify_zshrc_content(&content)
+            .content;
+        let lines: Vec<&str> = result.lines().collect();
+        let source = lines.iter().position(|l| l.starts_with("source")).unwrap();
+        let begin = lines
+            .iter()
+            .position(|l| *l == block::MANAGED.begin)
+            .unwrap();
+        assert!(begin > source);
+    }
+
+    #[test]
+    fn test_modify_zshrc_content_renders_aliases_path_source_and_fpath() {
+        let configurator = ZshrcConfigurator::new(
+            ZshConfig {
+                source: vec!["~/.zshrc.local".to_string()],
+                fpath: vec!["~/.zfunc".to_string()],
+                ..ZshConfig::default()
+            },
+            ShellConfig {
+                aliases: [("ll".to_string(), "ls -lah".to_string())].into(),
+                path_prepend: vec!["~/.cargo/bin".to_string()],
+                path_append: vec!["/opt/tools".to_string()],
+                ..ShellConfig::default()
+            },
+        );
+        let content = "export ZSH=\"$HOME/.oh-my-zsh\"\nsource $ZSH/oh-my-zsh.sh\n";
+        let result = configurator.modify_zshrc_content(content).content;
+        let lines: Vec<&str> = result.lines().collect();
+
+        let fpath = lines
+            .iter()
+            .position(|l| *l == "fpath=(\"$HOME/.zfunc\" $fpath)")
+            .unwrap();
+        let source =
+            layout::find_omz_source(&result.lines().map(String::from).collect::<Vec<_>>()).unwrap();
+        assert!(fpath < source);
+        for expected in [
+            "typeset -U path PATH",
+            "path=(\"$HOME/.cargo/bin\" $path)",
+            "path+=(\"/opt/tools\")",
+            "alias ll='ls -lah'",
+            "[[ -r \"$HOME/.zshrc.local\" ]] && source \"$HOME/.zshrc.local\"",
+        ] {
+            let pos = lines.iter().position(|l| *l == expected).unwrap();
+            assert!(pos > source, "{} should follow the source line", expected);
+        }
+    }
+
+    #[test]
+    fn test_settings_are_routed_to_their_startup_file() {
+        let configurator = ZshrcConfigurator::new(
+            ZshConfig {
+                env_files: [("HOMEBREW_NO_AUTO_UPDATE".to_string(), StartupFile::Zshenv)].into(),
+                path_file: Some(StartupFile::Zprofile),
+                ..ZshConfig::default()
+            },
+            ShellConfig {
+                env: [
+                    (
+                        "HOMEBREW_NO_AUTO_UPDATE".to_string(),
+                        EnvValue::Value("1".to_string()),
+                    ),
+                    ("EDITOR".to_string(), EnvValue::Value("hx".to_string())),
+                ]
+                .into(),
+                path_prepend: vec!["~/.cargo/bin".to_string()],
+                ..ShellConfig::default()
+            },
+        );
+
+        let zshrc = configurator
+            .modify_zshrc_content("source $ZSH/oh-my-zsh.sh\n")
+            .content;
+        assert!(zshrc.contains("export EDITOR=hx"));
+        assert!(!zshrc.contains("HOMEBREW_NO_AUTO_UPDATE") && !zshrc.contains("path="));
+
+        let zshenv = configurator.modify_startup_content(StartupFile::Zshenv, "# mine\n");
+        assert!(zshenv.starts_with("# mine\n"));
+        assert!(zshenv.contains("export HOMEBREW_NO_AUTO_UPDATE=1"));
+        assert!(!zshenv.contains("path="));
+
+        let zprofile = configurator.modify_startup_content(StartupFile::Zprofile, "");
+        assert!(zprofile.contains("path=(\"$HOME/.cargo/bin\" $path)"));
+        assert!(!zprofile.contains("export"));
+    }
+
+    #[test]
+    fn test_template_bootstraps_complete_zshrc() {
+        let configurator = configurator_with("stefc", &["z"], &[("EDITOR", "hx")]);
+        let result = configurator
+            .modify_zshrc_content(&ZshrcConfigurator::template())
+            .content;
+        let lines: Vec<String> = result.lines().map(String::from).collect();
+        let source = layout::find_omz_source(&lines).unwrap();
+        let theme = lines
+            .iter()
+            .position(|l| l == "ZSH_THEME=\"stefc\"")
+            .unwrap();
+        let plugins = lines.iter().position(|l| l == "plugins=(git z)").unwrap();
+        let export = lines.iter().position(|l| l == "export EDITOR=hx").unwrap();
+        assert!(lines[0].starts_with('#'));
+        assert!(theme < source && plugins < source && export > source);
+    }
+}
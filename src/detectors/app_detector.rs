@@ -1,11 +1,43 @@
+use crate::common::SetupResult;
 use crate::symlinks::SymlinkConfig;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Config file merged into a file the user keeps editing, such as VS Code's `settings.json`
+#[derive(Debug, Clone)]
+pub struct MergedFile {
+    pub source: PathBuf,
+    /// Destination with a leading `~` for the home directory
+    pub destination: String,
+}
 
 /// Trait for detecting if an application is installed
 pub trait AppDetector {
     fn is_installed(&self) -> bool;
     fn name(&self) -> &'static str;
+
+    /// Command line program the application provides, used to resolve env var preferences
+    fn command(&self) -> Option<&'static str> {
+        None
+    }
+
     fn symlinks(&self, _config_dir: &Path) -> Vec<SymlinkConfig> {
         vec![]
     }
+
+    /// Config files merged into the user's own file instead of being linked over it
+    fn merged_files(&self, _config_dir: &Path) -> Vec<MergedFile> {
+        vec![]
+    }
+
+    /// Every file in `config_dir` that is linked or merged
+    fn sources(&self, config_dir: &Path) -> Vec<PathBuf> {
+        let linked = self.symlinks(config_dir).into_iter().map(|c| c.source);
+        let merged = self.merged_files(config_dir).into_iter().map(|f| f.source);
+        linked.chain(merged).collect()
+    }
+
+    /// Check the linked or merged configuration with the application's own validator
+    fn validate(&self) -> SetupResult<()> {
+        Ok(())
+    }
 }
@@ -14,21 +14,25 @@ impl AppDetector for HelixDetector {
         "Helix"
     }
 
+    fn command(&self) -> Option<&'static str> {
+        Some("hx")
+    }
+
     fn symlinks(&self, config_dir: &Path) -> Vec<SymlinkConfig> {
         vec![
             SymlinkConfig {
                 source: config_dir.join("helix.config.toml"),
-                destination: "~/.config/helix/config.toml",
+                destination: "~/.config/helix/config.toml".to_string(),
                 installer_name: "Helix",
             },
             SymlinkConfig {
                 source: config_dir.join("helix-theme/warm-burnout-light.toml"),
-                destination: "~/.config/helix/themes/warm-burnout-light.toml",
+                destination: "~/.config/helix/themes/warm-burnout-light.toml".to_string(),
                 installer_name: "Helix-Warm Burnout",
             },
             SymlinkConfig {
                 source: config_dir.join("helix-theme/warm-burnout-dark.toml"),
-                destination: "~/.config/helix/themes/warm-burnout-dark.toml",
+                destination: "~/.config/helix/themes/warm-burnout-dark.toml".to_string(),
                 installer_name: "Helix-Warm Burnout",
             },
         ]
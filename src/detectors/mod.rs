@@ -2,6 +2,7 @@ pub mod app_detector;
 pub mod helix;
 pub mod mac_app;
 pub mod oh_my_zsh;
+pub mod omz;
 pub mod vscode;
 pub mod wezterm;
 pub mod which;
@@ -10,6 +11,32 @@ pub mod yazi;
 pub use app_detector::AppDetector;
 pub use helix::HelixDetector;
 pub use oh_my_zsh::OhMyZshDetector;
-pub use vscode::VSCodeDetector;
+pub use omz::OhMyZsh;
+pub use vscode::VSCODE_DETECTORS;
 pub use wezterm::WezTermDetector;
+use which::is_program_in_path;
 pub use yazi::YaziDetector;
+
+/// All detectors that contribute symlinks, in the order they are processed
+pub fn all_detectors() -> [&'static dyn AppDetector; 8] {
+    [
+        &WezTermDetector,
+        &OhMyZshDetector,
+        &VSCODE_DETECTORS[0],
+        &VSCODE_DETECTORS[1],
+        &VSCODE_DETECTORS[2],
+        &VSCODE_DETECTORS[3],
+        &YaziDetector,
+        &HelixDetector,
+    ]
+}
+
+/// Whether `program` can be run: it is on PATH and, when it belongs to a known
+/// application, that application's detector reports it as installed
+pub fn is_command_available(program: &str) -> bool {
+    is_program_in_path(program)
+        && all_detectors()
+            .iter()
+            .filter(|detector| detector.command() == Some(program))
+            .all(|detector| detector.is_installed())
+}
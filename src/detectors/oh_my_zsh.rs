@@ -1,18 +1,17 @@
-use crate::detectors::app_detector::AppDetector;
+use crate::common::{SetupResult, expand_home, replace_home_with_tilde, run_command};
+use crate::detectors::which::is_program_in_path;
+use crate::detectors::{OhMyZsh, app_detector::AppDetector};
 use crate::symlinks::SymlinkConfig;
-use std::env;
 use std::path::Path;
 
+/// Theme file linked into oh-my-zsh's themes directory
+const THEME: &str = "stefc.zsh-theme";
+
 pub struct OhMyZshDetector;
 
 impl AppDetector for OhMyZshDetector {
     fn is_installed(&self) -> bool {
-        if let Some(home_dir) = env::var_os("HOME") {
-            let home_path = Path::new(&home_dir);
-            home_path.join(".oh-my-zsh").exists()
-        } else {
-            false
-        }
+        OhMyZsh::locate().is_some()
     }
 
     fn name(&self) -> &'static str {
@@ -20,10 +19,26 @@ impl AppDetector for OhMyZshDetector {
     }
 
     fn symlinks(&self, config_dir: &Path) -> Vec<SymlinkConfig> {
+        // Honors `$ZSH` like oh-my-zsh itself
+        let destination = match OhMyZsh::locate() {
+            Some(omz) => replace_home_with_tilde(&omz.root.join("themes").join(THEME)),
+            None => format!("~/.oh-my-zsh/themes/{}", THEME),
+        };
         vec![SymlinkConfig {
-            source: config_dir.join("stefc.zsh-theme"),
-            destination: "~/.oh-my-zsh/themes/stefc.zsh-theme",
+            source: config_dir.join(THEME),
+            destination,
             installer_name: "oh-my-zsh",
         }]
     }
+
+    fn validate(&self) -> SetupResult<()> {
+        if !is_program_in_path("zsh") {
+            return Ok(());
+        }
+        for config in self.symlinks(Path::new("")) {
+            let theme = expand_home(&config.destination);
+            run_command("zsh", &["-n", &theme.to_string_lossy()])?;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,84 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+/// Location of an oh-my-zsh installation and its custom directory
+pub struct OhMyZsh {
+    pub root: PathBuf,
+    pub custom: PathBuf,
+}
+
+impl OhMyZsh {
+    /// Locate oh-my-zsh from `$ZSH` (default `~/.oh-my-zsh`) and `$ZSH_CUSTOM`
+    /// (default `$ZSH/custom`). Returns `None` when it is not installed.
+    pub fn locate() -> Option<Self> {
+        let root = env::var_os("ZSH")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".oh-my-zsh")))?;
+        let custom = env::var_os("ZSH_CUSTOM")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| root.join("custom"));
+        root.is_dir().then_some(Self { root, custom })
+    }
+
+    /// Theme file oh-my-zsh would load for `theme`, searched in the same order oh-my-zsh uses
+    pub fn theme_file(&self, theme: &str) -> Option<PathBuf> {
+        let file = format!("{}.zsh-theme", theme);
+        [
+            self.custom.join(&file),
+            self.custom.join("themes").join(&file),
+            self.root.join("themes").join(&file),
+        ]
+        .into_iter()
+        .find(|path| path.is_file())
+    }
+
+    /// Whether `plugin` exists as a built-in or custom plugin
+    pub fn has_plugin(&self, plugin: &str) -> bool {
+        [self.custom.join("plugins"), self.root.join("plugins")]
+            .iter()
+            .map(|dir| dir.join(plugin))
+            .any(|dir| {
+                dir.join(format!("{}.plugin.zsh", plugin)).is_file()
+                    || dir.join(format!("_{}", plugin)).is_file()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::TestDir;
+    use std::fs;
+
+    #[test]
+    fn test_resolves_builtin_and_custom_themes_and_plugins() {
+        let root = TestDir::new("omz");
+        let omz = OhMyZsh {
+            custom: root.join("custom"),
+            root: root.to_path_buf(),
+        };
+        fs::create_dir_all(root.join("themes")).unwrap();
+        fs::create_dir_all(root.join("custom/themes")).unwrap();
+        fs::create_dir_all(root.join("plugins/git")).unwrap();
+        fs::create_dir_all(root.join("custom/plugins/zsh-autosuggestions")).unwrap();
+        fs::write(root.join("themes/robbyrussell.zsh-theme"), "").unwrap();
+        fs::write(root.join("custom/themes/stefc.zsh-theme"), "").unwrap();
+        fs::write(root.join("plugins/git/git.plugin.zsh"), "").unwrap();
+        fs::write(
+            root.join("custom/plugins/zsh-autosuggestions/zsh-autosuggestions.plugin.zsh"),
+            "",
+        )
+        .unwrap();
+
+        assert!(omz.theme_file("robbyrussell").is_some());
+        assert!(omz.theme_file("stefc").is_some());
+        assert!(omz.theme_file("missing").is_none());
+        assert!(omz.has_plugin("git"));
+        assert!(omz.has_plugin("zsh-autosuggestions"));
+        assert!(!omz.has_plugin("missing"));
+    }
+}
@@ -1,24 +1,149 @@
-use crate::detectors::app_detector::AppDetector;
+use crate::common::{SetupError, SetupResult, expand_home, jsonc, replace_home_with_tilde};
+use crate::detectors::app_detector::{AppDetector, MergedFile};
 use crate::detectors::mac_app::is_mac_app_in_path;
-use crate::symlinks::SymlinkConfig;
-use std::path;
+use std::{fs, path};
 
-pub struct VSCodeDetector;
+/// A VS Code build: the app bundle, its command line tool and its user directory
+pub struct VscodeVariant {
+    pub name: &'static str,
+    /// App bundle name in `/Applications`
+    pub app: &'static str,
+    pub cli: &'static str,
+    /// Directory holding the user's settings, keybindings and snippets
+    pub user_dir: &'static str,
+}
+
+impl VscodeVariant {
+    /// Path of `name` in the user directory, with a leading `~`
+    pub fn file(&self, name: &str) -> String {
+        format!("{}/{}", self.user_dir, name)
+    }
+}
+
+/// Every supported VS Code variant
+pub static VARIANTS: [VscodeVariant; 4] = [
+    VscodeVariant {
+        name: "Visual Studio Code",
+        app: "Visual Studio Code",
+        cli: "code",
+        user_dir: "~/Library/Application Support/Code/User",
+    },
+    VscodeVariant {
+        name: "Visual Studio Code - Insiders",
+        app: "Visual Studio Code - Insiders",
+        cli: "code-insiders",
+        user_dir: "~/Library/Application Support/Code - Insiders/User",
+    },
+    VscodeVariant {
+        name: "VSCodium",
+        app: "VSCodium",
+        cli: "codium",
+        user_dir: "~/Library/Application Support/VSCodium/User",
+    },
+    VscodeVariant {
+        name: "Cursor",
+        app: "Cursor",
+        cli: "cursor",
+        user_dir: "~/Library/Application Support/Cursor/User",
+    },
+];
+
+/// Detector for one VS Code variant
+pub struct VSCodeDetector {
+    pub variant: &'static VscodeVariant,
+}
+
+/// One detector per entry in [`VARIANTS`]
+pub static VSCODE_DETECTORS: [VSCodeDetector; 4] = [
+    VSCodeDetector {
+        variant: &VARIANTS[0],
+    },
+    VSCodeDetector {
+        variant: &VARIANTS[1],
+    },
+    VSCodeDetector {
+        variant: &VARIANTS[2],
+    },
+    VSCodeDetector {
+        variant: &VARIANTS[3],
+    },
+];
+
+/// Detectors of the variants installed on this machine
+pub fn installed_variants() -> impl Iterator<Item = &'static VSCodeDetector> {
+    VSCODE_DETECTORS
+        .iter()
+        .filter(|detector| detector.is_installed())
+}
 
 impl AppDetector for VSCodeDetector {
     fn is_installed(&self) -> bool {
-        is_mac_app_in_path("Visual Studio Code")
+        is_mac_app_in_path(self.variant.app)
     }
 
     fn name(&self) -> &'static str {
-        "Visual Studio Code"
+        self.variant.name
+    }
+
+    fn command(&self) -> Option<&'static str> {
+        Some(self.variant.cli)
+    }
+
+    /// Settings, keybindings and snippets are merged, so Settings Sync and
+    /// personal tweaks never write into the team files
+    fn merged_files(&self, config_dir: &path::Path) -> Vec<MergedFile> {
+        let mut files = vec![
+            MergedFile {
+                source: config_dir.join("code.settings.json"),
+                destination: self.variant.file("settings.json"),
+            },
+            MergedFile {
+                source: config_dir.join("code.keybindings.json"),
+                destination: self.variant.file("keybindings.json"),
+            },
+        ];
+        // `code-snippets/<language>.json` holds the team snippets for one language
+        let mut snippets: Vec<path::PathBuf> = fs::read_dir(config_dir.join("code-snippets"))
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        snippets.sort();
+        for source in snippets {
+            if source.extension().is_some_and(|ext| ext == "json")
+                && let Some(name) = source.file_name()
+            {
+                let destination = self
+                    .variant
+                    .file(&format!("snippets/{}", name.to_string_lossy()));
+                files.push(MergedFile {
+                    source,
+                    destination,
+                });
+            }
+        }
+        files
     }
 
-    fn symlinks(&self, config_dir: &path::Path) -> Vec<SymlinkConfig> {
-        vec![SymlinkConfig {
-            source: config_dir.join("code.settings.json"),
-            destination: "~/Library/Application Support/Code/User/settings.json",
-            installer_name: "Visual Studio Code",
-        }]
+    /// Every settings, keybindings and snippets file present must parse
+    fn validate(&self) -> SetupResult<()> {
+        let user_dir = expand_home(self.variant.user_dir);
+        let mut paths = vec![
+            user_dir.join("settings.json"),
+            user_dir.join("keybindings.json"),
+        ];
+        if let Ok(entries) = fs::read_dir(user_dir.join("snippets")) {
+            paths.extend(entries.flatten().map(|entry| entry.path()));
+        }
+        for path in paths.iter().filter(|path| path.is_file()) {
+            let content = fs::read_to_string(path)?;
+            jsonc::parse(&content).map_err(|e| {
+                SetupError::Validation(format!(
+                    "{} {} {}",
+                    self.variant.name,
+                    replace_home_with_tilde(path),
+                    e
+                ))
+            })?;
+        }
+        Ok(())
     }
 }
@@ -14,21 +14,25 @@ impl AppDetector for WezTermDetector {
         "WezTerm"
     }
 
+    fn command(&self) -> Option<&'static str> {
+        Some("wezterm")
+    }
+
     fn symlinks(&self, config_dir: &path::Path) -> Vec<SymlinkConfig> {
         vec![
             SymlinkConfig {
                 source: config_dir.join(".wezterm.lua"),
-                destination: "~/.wezterm.lua",
+                destination: "~/.wezterm.lua".to_string(),
                 installer_name: "WezTerm",
             },
             SymlinkConfig {
                 source: config_dir.join("wezterm-theme/warm-burnout-light.toml"),
-                destination: "~/.config/wezterm/colors/warm-burnout-light.toml",
+                destination: "~/.config/wezterm/colors/warm-burnout-light.toml".to_string(),
                 installer_name: "WezTerm-Warm Burnout",
             },
             SymlinkConfig {
                 source: config_dir.join("wezterm-theme/warm-burnout-dark.toml"),
-                destination: "~/.config/wezterm/colors/warm-burnout-dark.toml",
+                destination: "~/.config/wezterm/colors/warm-burnout-dark.toml".to_string(),
                 installer_name: "WezTerm-Warm Burnout",
             },
         ]
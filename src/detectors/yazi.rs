@@ -14,10 +14,14 @@ impl AppDetector for YaziDetector {
         "Yazi"
     }
 
+    fn command(&self) -> Option<&'static str> {
+        Some("yazi")
+    }
+
     fn symlinks(&self, config_dir: &Path) -> Vec<SymlinkConfig> {
         vec![SymlinkConfig {
             source: config_dir.join("yazi.theme.toml"),
-            destination: "~/.config/yazi/theme.toml",
+            destination: "~/.config/yazi/theme.toml".to_string(),
             installer_name: self.name(),
         }]
     }
@@ -1,7 +1,11 @@
-use crate::common::{Log, Platform, replace_home_with_tilde};
+use crate::common::{ConfigDir, Log, Platform, replace_home_with_tilde};
 use std::env;
 
-pub fn log_environment_info(logger: &mut dyn Log, platform: &Platform) {
+pub fn log_environment_info(
+    logger: &mut dyn Log,
+    platform: &Platform,
+    config_dir: Option<&ConfigDir>,
+) {
     logger.info("▶ Environment");
 
     let mut items_count = 3;
@@ -9,6 +13,15 @@ pub fn log_environment_info(logger: &mut dyn Log, platform: &Platform) {
     logger.ok_with_highlight("Detected platform ->", &platform.to_string());
     logger.ok_with_highlight("Current working directory ->", &current_working_directory());
     logger.ok_with_highlight("Executable directory ->", &executable_directory());
+    if let Some(config_dir) = config_dir {
+        logger.ok_with_highlight(
+            &format!("Config directory ({}) ->", config_dir.source),
+            &replace_home_with_tilde(&config_dir.path),
+        );
+        items_count += 1;
+    } else {
+        logger.warn("Config directory not found");
+    }
 
     if let Some(serial) = platform.get_serial_number() {
         logger.ok_with_highlight("Serial number ->", &serial);
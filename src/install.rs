@@ -0,0 +1,59 @@
+use crate::{
+    assets,
+    common::{Log, SetupError, SetupResult, data_dir, replace_home_with_tilde},
+    symlinks::health,
+};
+use std::{env, fs, io, path::Path};
+
+/// Copy the running binary and unpack its embedded config assets into the data
+/// directory, then re-point all symlinks at the installed copy so they survive
+/// rebuilds and `cargo clean`.
+pub fn install(logger: &mut dyn Log) -> SetupResult<()> {
+    logger.info("▶ Install");
+
+    let data_dir = data_dir().ok_or_else(|| {
+        SetupError::Io(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Neither XDG_DATA_HOME nor HOME is set",
+        ))
+    })?;
+
+    let installed_config = data_dir.join("config");
+    let copied = assets::extract(&installed_config)?;
+    logger.ok_with_highlight(
+        &format!("Installed {} config assets ->", copied),
+        &replace_home_with_tilde(&installed_config),
+    );
+
+    // The config assets plus the binary when it was copied
+    let mut installed = copied;
+    let exe = env::current_exe()?;
+    let bin_dir = data_dir.join("bin");
+    let installed_exe = bin_dir.join(exe.file_name().unwrap_or("mac-setup".as_ref()));
+    if same_file(&exe, &installed_exe) {
+        logger.info("Binary is already installed");
+    } else {
+        fs::create_dir_all(&bin_dir)?;
+        // Copy next to the destination and rename, so an installed binary that
+        // is currently running can be replaced
+        let staging = bin_dir.join(".mac-setup.tmp");
+        fs::copy(&exe, &staging)?;
+        fs::rename(&staging, &installed_exe)?;
+        logger.ok_with_highlight(
+            "Installed binary ->",
+            &replace_home_with_tilde(&installed_exe),
+        );
+        installed += 1;
+    }
+
+    logger.add_group("Installed", installed);
+
+    health::repair_symlinks(logger, &installed_config)
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
@@ -1,34 +1,90 @@
 // no local io usage
 
+mod assets;
+mod bench;
+mod cli;
 mod common;
 mod configurators;
 mod detectors;
 mod environment;
+mod install;
 mod settings;
+mod shells;
 mod symlinks;
-use common::{Log, MemoryLogger, Platform, render_ui};
+mod user_config;
+mod validation;
+use cli::{Cli, Command};
+use common::{EditMode, Log, MemoryLogger, Platform, render_ui, resolve_config_dir};
+use configurators::{Configurator, ZshPluginsConfigurator, ZshrcConfigurator};
 use settings::apply_system_settings;
-use symlinks::{SetupResult, setup};
+use symlinks::{SetupResult, health, setup};
+use user_config::UserConfig;
 
 fn main() {
     let mut logger = MemoryLogger::default();
 
-    let result = execute(&mut logger);
+    let result = Cli::parse(std::env::args().skip(1)).and_then(|cli| execute(&mut logger, &cli));
     let snapshot = logger.snapshot();
     if let Err(e) = render_ui(&snapshot, result.as_ref().err().map(|e| e.to_string())) {
         eprintln!("Failed to render UI: {}", e);
     }
 }
 
-fn execute(logger: &mut dyn Log) -> SetupResult<()> {
+fn execute(logger: &mut dyn Log, cli: &Cli) -> SetupResult<()> {
     let platform = Platform::detect();
-    environment::log_environment_info(logger, &platform);
+    // Installing and extracting work from the embedded assets alone
+    let config_dir = resolve_config_dir(cli.config_dir.as_deref());
+    environment::log_environment_info(logger, &platform, config_dir.as_ref().ok());
 
-    // Apply platform-specific system settings
-    apply_system_settings(logger, &platform)?;
+    match &cli.command {
+        Command::Setup => {
+            let config_dir = config_dir?;
+            validation::validate_assets(logger, &config_dir.path)?;
+            let user_config = UserConfig::load(&config_dir.path)?;
 
-    configurators::run_configurators(logger)?;
-    setup::setup_symlinks(logger)?;
+            // A plan only previews file edits
+            if cli.mode != EditMode::Plan {
+                // Apply platform-specific system settings
+                apply_system_settings(logger, &platform)?;
+
+                // Symlinks first, so linked themes exist when .zshrc is checked
+                setup::setup_symlinks(logger, &config_dir.path)?;
+            }
+            configurators::run_configurators(logger, &user_config, &config_dir.path, cli.mode)?;
+        }
+        Command::Verify => {
+            health::verify_symlinks(logger, &config_dir?.path)?;
+        }
+        Command::Repair => {
+            let config_dir = config_dir?;
+            validation::validate_assets(logger, &config_dir.path)?;
+            health::repair_symlinks(logger, &config_dir.path)?;
+            health::verify_symlinks(logger, &config_dir.path)?;
+        }
+        Command::Install => {
+            install::install(logger)?;
+        }
+        Command::Extract(dest) => {
+            assets::extract_assets(logger, dest)?;
+        }
+        Command::UpdatePlugins => {
+            let user_config = UserConfig::load(&config_dir?.path)?;
+            ZshPluginsConfigurator::new(user_config.zsh.external_plugins)
+                .updating()
+                .run(logger)?;
+        }
+        Command::Bench { runs, profile } => {
+            let user_config = UserConfig::load(&config_dir?.path)?;
+            let shell = configurators::resolved_shell(logger, &user_config);
+            let zshrc = ZshrcConfigurator::new(user_config.zsh, shell).with_mode(cli.mode);
+            bench::bench(logger, &zshrc, *runs, *profile)?;
+        }
+        Command::Lint { fix } => {
+            let user_config = UserConfig::load(&config_dir?.path)?;
+            let policy = user_config.shell.symlinked_rc.unwrap_or_default();
+            ZshrcConfigurator::lint(logger, *fix, policy, cli.mode)?;
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,172 @@
+use std::ops::RangeInclusive;
+
+/// A block of lines owned by mac-setup, delimited by begin and end markers
+pub struct Block {
+    pub begin: &'static str,
+    pub end: &'static str,
+}
+
+/// Block holding the shared environment; in `.zshrc` it follows the oh-my-zsh source line
+pub const MANAGED: Block = Block {
+    begin: "# >>> mac-setup >>>",
+    end: "# <<< mac-setup <<<",
+};
+
+/// Block before the oh-my-zsh source line, for settings oh-my-zsh has to see
+pub const BEFORE_OMZ: Block = Block {
+    begin: "# >>> mac-setup (before oh-my-zsh) >>>",
+    end: "# <<< mac-setup (before oh-my-zsh) <<<",
+};
+
+const BLOCK_NOTE: &str = "# Managed by mac-setup, changes inside this block are overwritten";
+
+impl Block {
+    /// Line range of the block, including both markers
+    pub fn find(&self, lines: &[String]) -> Option<RangeInclusive<usize>> {
+        let start = lines.iter().position(|l| l.trim() == self.begin)?;
+        let end = lines[start..].iter().position(|l| l.trim() == self.end)? + start;
+        Some(start..=end)
+    }
+
+    /// Lines inside the block, without the markers and the note
+    pub fn body(&self, lines: &[String]) -> Vec<String> {
+        self.find(lines)
+            .map(|range| {
+                lines[*range.start() + 1..*range.end()]
+                    .iter()
+                    .filter(|l| l.trim() != BLOCK_NOTE)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Replace the block with `body`. Without an existing block, a new one is
+    /// inserted at `insert_pos`, separated from its neighbours by a blank line.
+    /// An empty body removes the block.
+    pub fn upsert(&self, lines: &mut Vec<String>, body: &[String], insert_pos: usize) {
+        if body.is_empty() {
+            self.remove(lines);
+            return;
+        }
+
+        let mut block = vec![self.begin.to_string(), BLOCK_NOTE.to_string()];
+        block.extend(body.iter().cloned());
+        block.push(self.end.to_string());
+
+        match self.find(lines) {
+            Some(range) => {
+                lines.splice(range, block);
+            }
+            None if insert_pos >= lines.len() => {
+                if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                    lines.push(String::new());
+                }
+                lines.extend(block);
+            }
+            None => {
+                if !lines[insert_pos].trim().is_empty() {
+                    block.push(String::new());
+                }
+                lines.splice(insert_pos..insert_pos, block);
+            }
+        }
+    }
+
+    /// Remove the block and one blank line separating it from its neighbours.
+    /// Returns whether a block was found.
+    pub fn remove(&self, lines: &mut Vec<String>) -> bool {
+        let Some(range) = self.find(lines) else {
+            return false;
+        };
+        let (mut start, mut end) = (*range.start(), *range.end());
+        if start > 0 && lines[start - 1].trim().is_empty() {
+            start -= 1;
+        } else if lines.get(end + 1).is_some_and(|l| l.trim().is_empty()) {
+            end += 1;
+        }
+        lines.drain(start..=end);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_upsert_appends_new_block() {
+        let mut lines = to_lines("source $ZSH/oh-my-zsh.sh");
+        let end = lines.len();
+        MANAGED.upsert(&mut lines, &["export EDITOR=hx".to_string()], end);
+        assert_eq!(
+            lines,
+            vec![
+                "source $ZSH/oh-my-zsh.sh",
+                "",
+                MANAGED.begin,
+                BLOCK_NOTE,
+                "export EDITOR=hx",
+                MANAGED.end
+            ]
+        );
+    }
+
+    #[test]
+    fn test_upsert_inserts_block_before_line() {
+        let mut lines = to_lines("export ZSH=~/.oh-my-zsh\nsource $ZSH/oh-my-zsh.sh");
+        BEFORE_OMZ.upsert(&mut lines, &["fpath=(~/.zfunc $fpath)".to_string()], 1);
+        assert_eq!(
+            lines,
+            vec![
+                "export ZSH=~/.oh-my-zsh",
+                BEFORE_OMZ.begin,
+                BLOCK_NOTE,
+                "fpath=(~/.zfunc $fpath)",
+                BEFORE_OMZ.end,
+                "",
+                "source $ZSH/oh-my-zsh.sh"
+            ]
+        );
+        assert!(BEFORE_OMZ.remove(&mut lines));
+        assert_eq!(
+            lines,
+            vec!["export ZSH=~/.oh-my-zsh", "source $ZSH/oh-my-zsh.sh"]
+        );
+    }
+
+    #[test]
+    fn test_upsert_replaces_existing_block() {
+        let mut lines = to_lines(&format!(
+            "a\n{}\nexport EDITOR=vi\n{}\nb",
+            MANAGED.begin, MANAGED.end
+        ));
+        MANAGED.upsert(&mut lines, &["export EDITOR=hx".to_string()], 0);
+        assert_eq!(
+            lines,
+            vec![
+                "a",
+                MANAGED.begin,
+                BLOCK_NOTE,
+                "export EDITOR=hx",
+                MANAGED.end,
+                "b"
+            ]
+        );
+        assert_eq!(MANAGED.body(&lines), vec!["export EDITOR=hx"]);
+    }
+
+    #[test]
+    fn test_upsert_with_empty_body_removes_block() {
+        let mut lines = to_lines(&format!(
+            "a\n\n{}\nexport X=1\n{}\nb",
+            MANAGED.begin, MANAGED.end
+        ));
+        MANAGED.upsert(&mut lines, &[], 0);
+        assert_eq!(lines, vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,183 @@
+pub mod block;
+pub mod quote;
+
+use crate::user_config::ShellConfig;
+use std::fmt;
+
+/// Shell dialect the shared environment is rendered for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Zsh,
+    Bash,
+    Fish,
+}
+
+impl Shell {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Shell::Zsh => "zsh",
+            Shell::Bash => "bash",
+            Shell::Fish => "fish",
+        }
+    }
+
+    /// Render the complete shared environment: PATH entries, exports and aliases
+    pub fn render(&self, config: &ShellConfig) -> Vec<String> {
+        let mut lines = self.path_lines(&config.path_prepend, &config.path_append);
+        lines.extend(
+            config
+                .env
+                .iter()
+                .map(|(key, value)| self.export_line(key, value.as_str())),
+        );
+        lines.extend(
+            config
+                .aliases
+                .iter()
+                .map(|(name, command)| self.alias_line(name, command)),
+        );
+        lines
+    }
+
+    /// Lines that add directories to PATH, skipping directories already present
+    pub fn path_lines(&self, prepend: &[String], append: &[String]) -> Vec<String> {
+        if prepend.is_empty() && append.is_empty() {
+            return Vec::new();
+        }
+
+        match self {
+            Shell::Zsh => {
+                // `typeset -U` drops duplicate entries, keeping the first occurrence
+                let mut lines = vec!["typeset -U path PATH".to_string()];
+                if !prepend.is_empty() {
+                    let dirs: Vec<String> = prepend.iter().map(|d| quote::path(d)).collect();
+                    lines.push(format!("path=({} $path)", dirs.join(" ")));
+                }
+                if !append.is_empty() {
+                    let dirs: Vec<String> = append.iter().map(|d| quote::path(d)).collect();
+                    lines.push(format!("path+=({})", dirs.join(" ")));
+                }
+                lines
+            }
+            Shell::Bash => {
+                let mut lines = Vec::new();
+                // Prepend in reverse so the first configured directory ends up first
+                for dir in prepend.iter().rev() {
+                    let dir = quote::path(dir);
+                    lines.push(format!(
+                        "case \":$PATH:\" in *:{}:*) ;; *) PATH={}:\"$PATH\" ;; esac",
+                        dir, dir
+                    ));
+                }
+                for dir in append {
+                    let dir = quote::path(dir);
+                    lines.push(format!(
+                        "case \":$PATH:\" in *:{}:*) ;; *) PATH=\"$PATH\":{} ;; esac",
+                        dir, dir
+                    ));
+                }
+                lines.push("export PATH".to_string());
+                lines
+            }
+            Shell::Fish => {
+                // fish_add_path skips directories that are already in PATH
+                let mut lines = Vec::new();
+                if !prepend.is_empty() {
+                    let dirs: Vec<String> = prepend.iter().map(|d| quote::fish_path(d)).collect();
+                    lines.push(format!(
+                        "fish_add_path --global --path --prepend {}",
+                        dirs.join(" ")
+                    ));
+                }
+                if !append.is_empty() {
+                    let dirs: Vec<String> = append.iter().map(|d| quote::fish_path(d)).collect();
+                    lines.push(format!(
+                        "fish_add_path --global --path --append {}",
+                        dirs.join(" ")
+                    ));
+                }
+                lines
+            }
+        }
+    }
+
+    pub fn export_line(&self, key: &str, value: &str) -> String {
+        match self {
+            Shell::Zsh | Shell::Bash => format!("export {}={}", key, quote::value(value)),
+            Shell::Fish => format!("set -gx {} {}", key, quote::fish_value(value)),
+        }
+    }
+
+    pub fn alias_line(&self, name: &str, command: &str) -> String {
+        match self {
+            Shell::Zsh | Shell::Bash => format!("alias {}={}", name, quote::single_quote(command)),
+            Shell::Fish => format!("alias {} {}", name, quote::fish_single_quote(command)),
+        }
+    }
+}
+
+impl fmt::Display for Shell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user_config::EnvValue;
+
+    fn sample_config() -> ShellConfig {
+        ShellConfig {
+            env: [(
+                "EDITOR".to_string(),
+                EnvValue::Value("code --wait".to_string()),
+            )]
+            .into(),
+            aliases: [("ll".to_string(), "ls -lah".to_string())].into(),
+            path_prepend: vec!["~/.cargo/bin".to_string()],
+            path_append: vec!["/opt/tools".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_bash() {
+        assert_eq!(
+            Shell::Bash.render(&sample_config()),
+            vec![
+                "case \":$PATH:\" in *:\"$HOME/.cargo/bin\":*) ;; *) PATH=\"$HOME/.cargo/bin\":\"$PATH\" ;; esac",
+                "case \":$PATH:\" in *:\"/opt/tools\":*) ;; *) PATH=\"$PATH\":\"/opt/tools\" ;; esac",
+                "export PATH",
+                "export EDITOR=\"code --wait\"",
+                "alias ll='ls -lah'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_fish() {
+        assert_eq!(
+            Shell::Fish.render(&sample_config()),
+            vec![
+                "fish_add_path --global --path --prepend \"$HOME/.cargo/bin\"",
+                "fish_add_path --global --path --append \"/opt/tools\"",
+                "set -gx EDITOR \"code --wait\"",
+                "alias ll 'ls -lah'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_variables_expand_in_every_shell() {
+        assert_eq!(
+            [Shell::Zsh, Shell::Bash, Shell::Fish]
+                .map(|shell| shell.export_line("GOPATH", "$HOME/go")),
+            [
+                "export GOPATH=\"$HOME/go\"",
+                "export GOPATH=\"$HOME/go\"",
+                "set -gx GOPATH \"$HOME/go\"",
+            ]
+        );
+    }
+}
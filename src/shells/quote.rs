@@ -0,0 +1,108 @@
+//! Quoting of configured values for zsh/bash and for fish. Values and paths
+//! are double-quoted in every shell, so `$VAR` expands the same way in each;
+//! alias commands are single-quoted and expand when the alias runs.
+
+/// Quote a value for an assignment, leaving simple words unquoted
+pub fn value(value: &str) -> String {
+    if is_simple(value) {
+        value.to_string()
+    } else {
+        double_quote(value)
+    }
+}
+
+/// Wrap in double quotes, escaping characters that are special inside them
+/// while still allowing `$VAR` expansion
+pub fn double_quote(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '`') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+/// Wrap in single quotes, so the value is taken literally
+pub fn single_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Quote a path, turning a leading `~/` into `$HOME/` so it expands inside quotes
+pub fn path(path: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => double_quote(&format!("$HOME/{}", rest)),
+        None if path == "~" => "\"$HOME\"".to_string(),
+        None => double_quote(path),
+    }
+}
+
+/// Quote a value for fish, leaving simple words unquoted
+pub fn fish_value(value: &str) -> String {
+    if is_simple(value) {
+        value.to_string()
+    } else {
+        fish_double_quote(value)
+    }
+}
+
+/// Wrap in fish double quotes, which expand `$VAR` like zsh and bash do
+pub fn fish_double_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', r"\\").replace('"', "\\\""))
+}
+
+/// Wrap in fish single quotes, so the value is taken literally
+pub fn fish_single_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+/// Quote a path for fish; fish expands `$HOME` inside double quotes
+pub fn fish_path(path: &str) -> String {
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => format!("$HOME/{}", rest),
+        None if path == "~" => "$HOME".to_string(),
+        None => path.to_string(),
+    };
+    fish_double_quote(&path)
+}
+
+/// Whether a value can be written without quotes in any supported shell
+fn is_simple(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:@%+,".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_quotes_only_when_needed() {
+        assert_eq!(value("hx"), "hx");
+        assert_eq!(value("code --wait"), "\"code --wait\"");
+        assert_eq!(value("a\"b"), "\"a\\\"b\"");
+    }
+
+    #[test]
+    fn test_single_quote_escapes_quotes() {
+        assert_eq!(single_quote("it's"), r"'it'\''s'");
+    }
+
+    #[test]
+    fn test_path_expands_home() {
+        assert_eq!(path("~/.cargo/bin"), "\"$HOME/.cargo/bin\"");
+        assert_eq!(path("/opt/homebrew/bin"), "\"/opt/homebrew/bin\"");
+    }
+
+    #[test]
+    fn test_fish_quoting() {
+        assert_eq!(fish_value("hx"), "hx");
+        assert_eq!(fish_value("it's here"), "\"it's here\"");
+        assert_eq!(fish_single_quote("it's"), r"'it\'s'");
+        assert_eq!(fish_path("~/.cargo/bin"), "\"$HOME/.cargo/bin\"");
+    }
+}
@@ -0,0 +1,269 @@
+use crate::{
+    common::{Log, expand_home},
+    detectors::all_detectors,
+    symlinks::{SetupResult, SymlinkConfig, setup::symlink_create},
+};
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// State of a managed symlink on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkHealth {
+    /// The link points at its expected source file
+    Healthy,
+    /// Nothing exists at the destination
+    Missing,
+    /// The link points at the expected source, but that source is missing from `config/`
+    SourceMissing,
+    /// The link points at a path that no longer exists
+    Dangling(PathBuf),
+    /// The link points at an existing file other than the expected source
+    Stale(PathBuf),
+    /// The destination or the source is not the kind of file expected
+    WrongType(String),
+}
+
+impl LinkHealth {
+    pub fn is_healthy(&self) -> bool {
+        *self == LinkHealth::Healthy
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            LinkHealth::Healthy => "healthy".to_string(),
+            LinkHealth::Missing => "missing".to_string(),
+            LinkHealth::SourceMissing => "source file is missing".to_string(),
+            LinkHealth::Dangling(target) if is_build_output(target) => {
+                format!(
+                    "dangling, points into a build directory: {}",
+                    target.display()
+                )
+            }
+            LinkHealth::Dangling(target) => format!("dangling, points at {}", target.display()),
+            LinkHealth::Stale(target) if is_build_output(target) => {
+                format!("stale, points into a build directory: {}", target.display())
+            }
+            LinkHealth::Stale(target) => format!("stale, points at {}", target.display()),
+            LinkHealth::WrongType(kind) => format!("wrong file type: {}", kind),
+        }
+    }
+}
+
+/// Health of one known symlink together with the detector it belongs to
+pub struct HealthReport {
+    pub config: SymlinkConfig,
+    pub installed: bool,
+    pub health: LinkHealth,
+}
+
+/// Inspect every known symlink. Missing links of apps that are not installed are left out.
+pub fn check_symlinks(config_dir: &Path) -> Vec<HealthReport> {
+    let mut reports = Vec::new();
+    for detector in all_detectors() {
+        let installed = detector.is_installed();
+        for config in detector.symlinks(config_dir) {
+            let health = inspect(&config);
+            if health == LinkHealth::Missing && !installed {
+                continue;
+            }
+            reports.push(HealthReport {
+                config,
+                installed,
+                health,
+            });
+        }
+    }
+    reports
+}
+
+/// Log the health of every known symlink and return the number of broken ones
+pub fn verify_symlinks(logger: &mut dyn Log, config_dir: &Path) -> SetupResult<usize> {
+    logger.info("▶ Verify Symlinks");
+
+    let mut broken = 0;
+    for report in check_symlinks(config_dir) {
+        if report.health.is_healthy() {
+            logger.ok_with_highlight("Symlink healthy ->", &report.config.destination);
+        } else {
+            logger.warn(&format!(
+                "{}: {}",
+                &report.config.destination,
+                report.health.describe()
+            ));
+            broken += 1;
+        }
+    }
+
+    logger.add_group("Broken symlinks", broken);
+    Ok(broken)
+}
+
+/// Re-point every broken symlink at its expected source
+pub fn repair_symlinks(logger: &mut dyn Log, config_dir: &Path) -> SetupResult<()> {
+    logger.info("▶ Repair Symlinks");
+
+    let mut repaired = 0;
+    for report in check_symlinks(config_dir) {
+        let config = &report.config;
+        let result = match &report.health {
+            LinkHealth::Healthy => continue,
+            LinkHealth::Missing if !report.installed => continue,
+            LinkHealth::SourceMissing => {
+                logger.warn(&format!(
+                    "Cannot repair {}: source {} is missing",
+                    &config.destination,
+                    config.source.display()
+                ));
+                continue;
+            }
+            _ if !config.source.is_file() => {
+                logger.warn(&format!(
+                    "Cannot repair {}: source {} is not a file",
+                    &config.destination,
+                    config.source.display()
+                ));
+                continue;
+            }
+            LinkHealth::WrongType(_) => move_aside(config).and_then(|_| symlink_create(config)),
+            _ => symlink_create(config),
+        };
+
+        match result {
+            Ok(()) => {
+                logger.ok_with_highlight("Symlink repaired ->", &config.destination);
+                repaired += 1;
+            }
+            Err(e) => logger.warn(&format!(
+                "Failed to repair symlink for {}: {}",
+                config.installer_name, e
+            )),
+        }
+    }
+
+    logger.add_group("Repaired symlinks", repaired);
+    Ok(())
+}
+
+/// Determine the health of a single symlink
+pub fn inspect(config: &SymlinkConfig) -> LinkHealth {
+    let dest = expand_home(&config.destination);
+    let Ok(meta) = fs::symlink_metadata(&dest) else {
+        return LinkHealth::Missing;
+    };
+
+    if !meta.file_type().is_symlink() {
+        let kind = if meta.is_dir() {
+            "directory instead of a symlink"
+        } else {
+            "regular file instead of a symlink"
+        };
+        return LinkHealth::WrongType(kind.to_string());
+    }
+
+    let target = match fs::read_link(&dest) {
+        Ok(target) if target.is_relative() => dest
+            .parent()
+            .map(|parent| parent.join(&target))
+            .unwrap_or(target),
+        Ok(target) => target,
+        Err(_) => return LinkHealth::Dangling(dest),
+    };
+
+    if same_path(&target, &config.source) {
+        if config.source.is_file() {
+            LinkHealth::Healthy
+        } else if config.source.exists() {
+            LinkHealth::WrongType("source is not a file".to_string())
+        } else {
+            LinkHealth::SourceMissing
+        }
+    } else if target.exists() {
+        LinkHealth::Stale(target)
+    } else {
+        LinkHealth::Dangling(target)
+    }
+}
+
+/// Keep a regular file that sits where a symlink belongs by renaming it to `*.bak`
+fn move_aside(config: &SymlinkConfig) -> SetupResult<()> {
+    let dest = expand_home(&config.destination);
+    if dest.is_dir() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            "destination is a directory",
+        )
+        .into());
+    }
+    let mut backup = dest.clone().into_os_string();
+    backup.push(".bak");
+    fs::rename(&dest, backup)?;
+    Ok(())
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Whether a path lies in a Cargo `target/<profile>/config` directory
+pub fn is_build_output(path: &Path) -> bool {
+    let components: Vec<Component> = path.components().collect();
+    components.windows(3).any(|w| {
+        w[0].as_os_str() == "target"
+            && (w[1].as_os_str() == "debug" || w[1].as_os_str() == "release")
+            && w[2].as_os_str() == "config"
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::common::TestDir;
+    use std::os::unix::fs::symlink;
+
+    fn config_for(source: PathBuf, dest: &Path) -> SymlinkConfig {
+        SymlinkConfig {
+            source,
+            destination: dest.display().to_string(),
+            installer_name: "test",
+        }
+    }
+
+    #[test]
+    fn test_is_build_output() {
+        assert!(is_build_output(Path::new(
+            "/src/mac-setup/target/debug/config/x.toml"
+        )));
+        assert!(!is_build_output(Path::new("/src/mac-setup/config/x.toml")));
+    }
+
+    #[test]
+    fn test_inspect_link_states() {
+        let dir = TestDir::new("health");
+        let source = dir.join("theme.toml");
+        fs::write(&source, "x").unwrap();
+        let dest = dir.join("link.toml");
+        let config = config_for(source.clone(), &dest);
+
+        assert_eq!(inspect(&config), LinkHealth::Missing);
+
+        symlink(&source, &dest).unwrap();
+        assert_eq!(inspect(&config), LinkHealth::Healthy);
+
+        fs::remove_file(&source).unwrap();
+        assert_eq!(inspect(&config), LinkHealth::SourceMissing);
+
+        fs::remove_file(&dest).unwrap();
+        let old = dir.join("target/debug/config/theme.toml");
+        symlink(&old, &dest).unwrap();
+        assert_eq!(inspect(&config), LinkHealth::Dangling(old));
+
+        fs::remove_file(&dest).unwrap();
+        fs::write(&dest, "copy").unwrap();
+        assert!(matches!(inspect(&config), LinkHealth::WrongType(_)));
+    }
+}
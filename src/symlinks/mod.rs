@@ -1,3 +1,4 @@
+pub mod health;
 pub mod setup;
 
 pub use crate::common::SetupResult;
@@ -7,6 +8,6 @@ use std::path::PathBuf;
 #[derive(Clone)]
 pub struct SymlinkConfig {
     pub source: PathBuf,
-    pub destination: &'static str,
+    pub destination: String,
     pub installer_name: &'static str,
 }
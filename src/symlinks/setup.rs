@@ -1,45 +1,60 @@
 use crate::{
-    common::Log,
-    detectors::{
-        AppDetector, HelixDetector, OhMyZshDetector, VSCodeDetector, WezTermDetector, YaziDetector,
-    },
+    common::{Backup, Log, SetupError, expand_home},
+    detectors::all_detectors,
     symlinks::{SetupResult, SymlinkConfig},
 };
-use std::{env, fs, io, path::{Path, PathBuf}};
+use std::{fs, io, path::Path};
 
-pub fn setup_symlinks(logger: &mut dyn Log) -> SetupResult<()> {
+pub fn setup_symlinks(logger: &mut dyn Log, config_dir: &Path) -> SetupResult<()> {
     logger.info("▶ Create Symlinks");
 
-    let exe_path = env::current_exe().expect("Failed to get executable path");
-    let exe_dir = exe_path
-        .parent()
-        .expect("Failed to get executable directory");
-    let config_dir = exe_dir.join("config");
-
-    let detectors: [&dyn AppDetector; 5] = [
-        &WezTermDetector,
-        &OhMyZshDetector,
-        &VSCodeDetector,
-        &YaziDetector,
-        &HelixDetector,
-    ];
-
     let mut affected = 0;
 
-    for detector in detectors {
+    for detector in all_detectors() {
         if detector.is_installed() {
-            let symlinks = detector.symlinks(&config_dir);
+            let symlinks = detector.symlinks(config_dir);
+            let mut backups = Vec::new();
+            let mut created = Vec::new();
             for config in symlinks {
-                if let Err(e) = symlink_create(&config) {
+                let result = Backup::capture(&expand_home(&config.destination))
+                    .map_err(SetupError::from)
+                    .and_then(|backup| {
+                        backups.push(backup);
+                        symlink_create(&config)
+                    });
+                if let Err(e) = result {
                     logger.warn(&format!(
                         "Failed to create symlink for {}: {}",
                         config.installer_name, e
                     ));
                 } else {
-                    logger.ok_with_highlight("Symlink created successfully", config.destination);
-                    affected += 1;
+                    created.push(config.destination.clone());
                 }
             }
+
+            if created.is_empty() {
+                continue;
+            }
+
+            if let Err(e) = detector.validate() {
+                logger.warn(&format!(
+                    "{} rejected the linked configuration: {}",
+                    detector.name(),
+                    e
+                ));
+                for backup in &backups {
+                    if let Err(e) = backup.restore() {
+                        logger.warn(&format!("Failed to roll back symlink: {}", e));
+                    }
+                }
+                logger.warn(&format!("Rolled back {} symlinks", detector.name()));
+                continue;
+            }
+
+            for destination in created {
+                logger.ok_with_highlight("Symlink created successfully", &destination);
+                affected += 1;
+            }
         } else {
             logger.warn(&format!(
                 "{} is not installed, skipping symlink creation.",
@@ -52,12 +67,8 @@ pub fn setup_symlinks(logger: &mut dyn Log) -> SetupResult<()> {
     Ok(())
 }
 
-fn symlink_create(config: &SymlinkConfig) -> SetupResult<()> {
-    let dest_str = config.destination;
-    let dest_expanded = dest_str
-        .strip_prefix("~/")
-        .and_then(|stripped| env::var_os("HOME").map(|home| Path::new(&home).join(stripped)))
-        .unwrap_or_else(|| PathBuf::from(dest_str));
+pub(crate) fn symlink_create(config: &SymlinkConfig) -> SetupResult<()> {
+    let dest_expanded = expand_home(&config.destination);
 
     if let Some(parent) = dest_expanded.parent() {
         fs::create_dir_all(parent)?;
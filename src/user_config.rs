@@ -0,0 +1,455 @@
+use crate::{
+    assets,
+    common::{SetupError, SetupResult, SymlinkPolicy, config_home, jsonc},
+};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Team configuration file, looked up in the config directory
+pub const TEAM_CONFIG_FILE: &str = "mac-setup.toml";
+
+/// Settings that drive the configurators. The team file in the config
+/// directory is loaded first; the personal file in
+/// `$XDG_CONFIG_HOME/mac-setup/config.toml` is layered on top of it.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UserConfig {
+    pub shell: ShellConfig,
+    pub zsh: ZshConfig,
+    pub vscode: VscodeConfig,
+}
+
+/// Environment shared by every supported shell, rendered into each shell's syntax
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShellConfig {
+    pub env: BTreeMap<String, EnvValue>,
+    pub aliases: BTreeMap<String, String>,
+    /// Directories put in front of `PATH`
+    pub path_prepend: Vec<String>,
+    /// Directories put at the end of `PATH`
+    pub path_append: Vec<String>,
+    /// How to edit startup files that are symlinks; `follow` when unset
+    pub symlinked_rc: Option<SymlinkPolicy>,
+}
+
+/// Value of an environment variable: a plain string, or a preference list such as
+/// `["hx", "code --wait", "vi"]` whose first entry with an available program wins
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum EnvValue {
+    Value(String),
+    Prefer(Vec<String>),
+}
+
+impl EnvValue {
+    /// The value to export; a preference list that was not resolved uses its last entry
+    pub fn as_str(&self) -> &str {
+        match self {
+            EnvValue::Value(value) => value,
+            EnvValue::Prefer(candidates) => candidates.last().map(String::as_str).unwrap_or(""),
+        }
+    }
+
+    /// First candidate whose program (its first word) is available, or the
+    /// last candidate as the fallback
+    fn resolve(&self, available: &impl Fn(&str) -> bool) -> &str {
+        match self {
+            EnvValue::Value(value) => value,
+            EnvValue::Prefer(candidates) => candidates
+                .iter()
+                .find(|candidate| candidate.split_whitespace().next().is_some_and(available))
+                .map(String::as_str)
+                .unwrap_or_else(|| self.as_str()),
+        }
+    }
+}
+
+impl ShellConfig {
+    /// Replace preference lists by the value chosen for this machine,
+    /// returning the chosen `(name, value)` pairs
+    pub fn resolve_env(&mut self, available: impl Fn(&str) -> bool) -> Vec<(String, String)> {
+        let mut chosen = Vec::new();
+        for (key, value) in self.env.iter_mut() {
+            if let EnvValue::Prefer(_) = value {
+                let resolved = value.resolve(&available).to_string();
+                chosen.push((key.clone(), resolved.clone()));
+                *value = EnvValue::Value(resolved);
+            }
+        }
+        chosen
+    }
+}
+
+/// zsh and oh-my-zsh specific setup applied to `.zshrc`
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ZshConfig {
+    /// oh-my-zsh theme; `ZSH_THEME` is left untouched when unset
+    pub theme: Option<String>,
+    pub plugins: Vec<String>,
+    /// Extra files sourced when they exist
+    pub source: Vec<String>,
+    /// Directories added to `fpath` before oh-my-zsh runs `compinit`
+    pub fpath: Vec<String>,
+    /// Plugins cloned into `$ZSH_CUSTOM/plugins` and enabled alongside `plugins`
+    pub external_plugins: Vec<ExternalPlugin>,
+    /// Startup file each `[shell.env]` variable is exported from; `.zshrc` when not listed
+    pub env_files: BTreeMap<String, StartupFile>,
+    /// Startup file the `PATH` entries are added in; `.zshrc` when unset
+    pub path_file: Option<StartupFile>,
+}
+
+/// zsh startup file in `$ZDOTDIR` (or the home directory) a setting is written to
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StartupFile {
+    /// Read by every zsh, including scripts
+    Zshenv,
+    /// Read by login shells, after macOS' `path_helper` has set up `PATH`
+    Zprofile,
+    /// Read by interactive shells
+    #[default]
+    Zshrc,
+}
+
+impl StartupFile {
+    pub fn file_name(self) -> &'static str {
+        match self {
+            StartupFile::Zshenv => ".zshenv",
+            StartupFile::Zprofile => ".zprofile",
+            StartupFile::Zshrc => ".zshrc",
+        }
+    }
+}
+
+/// zsh plugin that does not ship with oh-my-zsh
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExternalPlugin {
+    /// Directory name under `$ZSH_CUSTOM/plugins`, also the name put into `plugins=(...)`
+    pub name: String,
+    /// Git URL to clone from, `file://` mirrors included
+    pub url: String,
+    /// Branch, tag or commit to check out; the remote's default branch when unset
+    #[serde(rename = "ref")]
+    pub git_ref: Option<String>,
+}
+
+impl ExternalPlugin {
+    /// Whether the name stays inside the plugins directory
+    fn has_valid_name(&self) -> bool {
+        let name = &self.name;
+        !name.is_empty()
+            && !name.starts_with('.')
+            && !name.contains(['/', '\\'])
+            && !name.contains("..")
+    }
+}
+
+impl ZshConfig {
+    /// Built-in plugins followed by the external ones, without duplicates
+    pub fn plugin_names(&self) -> Vec<String> {
+        let mut names = self.plugins.clone();
+        extend_unique(
+            &mut names,
+            self.external_plugins
+                .iter()
+                .map(|p| p.name.clone())
+                .collect(),
+        );
+        names
+    }
+}
+
+/// VS Code extensions kept installed
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VscodeConfig {
+    /// Extension IDs, optionally pinned as `publisher.name@version`
+    pub extensions: Vec<String>,
+    /// Uninstall extensions that are neither listed nor kept
+    pub prune: Option<bool>,
+    /// Extensions never pruned; `publisher.*` keeps all of a publisher's extensions
+    pub keep: Vec<String>,
+    /// VS Code profiles by name, each installing `extensions` plus its own
+    pub profiles: BTreeMap<String, VscodeProfile>,
+}
+
+/// Extensions of one VS Code profile, installed with `--profile`
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VscodeProfile {
+    pub extensions: Vec<String>,
+}
+
+impl UserConfig {
+    /// Load the team configuration from `config_dir` (or the copy embedded in
+    /// the binary) and layer the personal configuration on top
+    pub fn load(config_dir: &Path) -> SetupResult<Self> {
+        let team_path = config_dir.join(TEAM_CONFIG_FILE);
+        let mut config = if team_path.is_file() {
+            Self::from_file(&team_path)?
+        } else {
+            let embedded = assets::find(TEAM_CONFIG_FILE)
+                .map(|asset| String::from_utf8_lossy(asset.contents).into_owned())
+                .unwrap_or_default();
+            Self::parse(&embedded, Path::new(TEAM_CONFIG_FILE))?
+        };
+
+        if let Some(personal_path) = personal_config_path().filter(|p| p.is_file()) {
+            config.merge(Self::from_file(&personal_path)?);
+        }
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> SetupResult<Self> {
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content, path)
+    }
+
+    fn parse(content: &str, path: &Path) -> SetupResult<Self> {
+        let config: Self = toml::from_str(content).map_err(|e| {
+            let location = match e.span() {
+                Some(span) => format!(
+                    "{}:{}",
+                    path.display(),
+                    jsonc::line_col(content, span.start).0
+                ),
+                None => path.display().to_string(),
+            };
+            SetupError::Config(format!("{}: {}", location, e.message()))
+        })?;
+        // The name becomes a directory under `$ZSH_CUSTOM/plugins`
+        if let Some(plugin) = config
+            .zsh
+            .external_plugins
+            .iter()
+            .find(|p| !p.has_valid_name())
+        {
+            return Err(SetupError::Config(format!(
+                "{}: external plugin name '{}' must be a plain directory name",
+                path.display(),
+                plugin.name
+            )));
+        }
+        Ok(config)
+    }
+
+    /// Layer `other` on top: scalars and map entries from `other` win,
+    /// lists are extended without duplicates and external plugins are matched by name
+    pub fn merge(&mut self, other: UserConfig) {
+        let (shell, theirs) = (&mut self.shell, other.shell);
+        shell.env.extend(theirs.env);
+        shell.aliases.extend(theirs.aliases);
+        extend_unique(&mut shell.path_prepend, theirs.path_prepend);
+        extend_unique(&mut shell.path_append, theirs.path_append);
+        if theirs.symlinked_rc.is_some() {
+            shell.symlinked_rc = theirs.symlinked_rc;
+        }
+
+        let (zsh, theirs) = (&mut self.zsh, other.zsh);
+        if theirs.theme.is_some() {
+            zsh.theme = theirs.theme;
+        }
+        extend_unique(&mut zsh.plugins, theirs.plugins);
+        extend_unique(&mut zsh.source, theirs.source);
+        extend_unique(&mut zsh.fpath, theirs.fpath);
+        zsh.env_files.extend(theirs.env_files);
+        if theirs.path_file.is_some() {
+            zsh.path_file = theirs.path_file;
+        }
+        for plugin in theirs.external_plugins {
+            match zsh
+                .external_plugins
+                .iter_mut()
+                .find(|p| p.name == plugin.name)
+            {
+                Some(existing) => *existing = plugin,
+                None => zsh.external_plugins.push(plugin),
+            }
+        }
+
+        let (vscode, theirs) = (&mut self.vscode, other.vscode);
+        merge_extensions(&mut vscode.extensions, theirs.extensions);
+        for (name, profile) in theirs.profiles {
+            let existing = vscode.profiles.entry(name).or_default();
+            merge_extensions(&mut existing.extensions, profile.extensions);
+        }
+        if theirs.prune.is_some() {
+            vscode.prune = theirs.prune;
+        }
+        extend_unique(&mut vscode.keep, theirs.keep);
+    }
+}
+
+/// Personal configuration file: `$XDG_CONFIG_HOME/mac-setup/config.toml`,
+/// falling back to `~/.config/mac-setup/config.toml`
+pub fn personal_config_path() -> Option<PathBuf> {
+    config_home().map(|dir| dir.join("mac-setup").join("config.toml"))
+}
+
+/// A personal entry for the same extension replaces the team's pin
+fn merge_extensions(list: &mut Vec<String>, specs: Vec<String>) {
+    for spec in specs {
+        let id = spec.split('@').next().unwrap_or_default();
+        match list.iter_mut().find(|s| {
+            s.split('@')
+                .next()
+                .unwrap_or_default()
+                .eq_ignore_ascii_case(id)
+        }) {
+            Some(existing) => *existing = spec,
+            None => list.push(spec),
+        }
+    }
+}
+
+fn extend_unique(list: &mut Vec<String>, items: Vec<String>) {
+    for item in items {
+        if !list.contains(&item) {
+            list.push(item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shipped_team_config_parses() {
+        let config = UserConfig::from_file(&Path::new("config").join(TEAM_CONFIG_FILE)).unwrap();
+        assert_eq!(config.zsh.theme.as_deref(), Some("stefc"));
+        assert_eq!(
+            config.shell.env.get("EDITOR"),
+            Some(&EnvValue::Prefer(vec![
+                "hx".to_string(),
+                "code --wait".to_string(),
+                "vi".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_merge_personal_over_team() {
+        let mut team = UserConfig::parse(
+            "[zsh]\ntheme = \"stefc\"\nplugins = [\"z\", \"gh\"]\n[shell]\nenv = { EDITOR = \"hx\", A = \"1\" }",
+            Path::new("team.toml"),
+        )
+        .unwrap();
+        let personal = UserConfig::parse(
+            "[zsh]\nplugins = [\"gh\", \"docker\"]\n[shell.env]\nEDITOR = \"vim\"",
+            Path::new("personal.toml"),
+        )
+        .unwrap();
+        team.merge(personal);
+        assert_eq!(team.zsh.theme.as_deref(), Some("stefc"));
+        assert_eq!(team.zsh.plugins, vec!["z", "gh", "docker"]);
+        assert_eq!(
+            team.shell.env.get("EDITOR").map(EnvValue::as_str),
+            Some("vim")
+        );
+        assert_eq!(team.shell.env.get("A").map(EnvValue::as_str), Some("1"));
+    }
+
+    #[test]
+    fn test_merge_external_plugins_by_name() {
+        let mut team = UserConfig::parse(
+            "[[zsh.external_plugins]]\nname = \"zsh-autosuggestions\"\nurl = \"https://example.com/a.git\"\nref = \"v0.7.0\"",
+            Path::new("team.toml"),
+        )
+        .unwrap();
+        let personal = UserConfig::parse(
+            "[zsh]\nplugins = [\"git\"]\n[[zsh.external_plugins]]\nname = \"zsh-autosuggestions\"\nurl = \"file:///mirror/a.git\"\n[[zsh.external_plugins]]\nname = \"zsh-syntax-highlighting\"\nurl = \"https://example.com/b.git\"",
+            Path::new("personal.toml"),
+        )
+        .unwrap();
+        team.merge(personal);
+        assert_eq!(team.zsh.external_plugins.len(), 2);
+        assert_eq!(team.zsh.external_plugins[0].url, "file:///mirror/a.git");
+        assert_eq!(team.zsh.external_plugins[0].git_ref, None);
+        assert_eq!(
+            team.zsh.plugin_names(),
+            vec!["git", "zsh-autosuggestions", "zsh-syntax-highlighting"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_env_preference_list() {
+        let mut config = UserConfig::parse(
+            "[shell.env]\nEDITOR = [\"hx\", \"code --wait\", \"vi\"]\nPAGER = \"less\"",
+            Path::new("x.toml"),
+        )
+        .unwrap();
+        let mut without_hx = config.shell.clone();
+
+        let chosen = config.shell.resolve_env(|program| program == "hx");
+        assert_eq!(chosen, vec![("EDITOR".to_string(), "hx".to_string())]);
+        without_hx.resolve_env(|program| program == "code");
+        assert_eq!(without_hx.env["EDITOR"].as_str(), "code --wait");
+        assert_eq!(without_hx.env["PAGER"].as_str(), "less");
+
+        let mut nothing = UserConfig::parse(
+            "[shell.env]\nEDITOR = [\"hx\", \"vi\"]",
+            Path::new("x.toml"),
+        )
+        .unwrap()
+        .shell;
+        nothing.resolve_env(|_| false);
+        assert_eq!(nothing.env["EDITOR"], EnvValue::Value("vi".to_string()));
+    }
+
+    #[test]
+    fn test_merge_vscode_extensions_case_insensitively() {
+        let mut team = UserConfig::parse(
+            "[vscode]\nextensions = [\"rust-lang.rust-analyzer@0.3.1\", \"github.copilot-chat\"]\n[vscode.profiles.rust]\nextensions = [\"a.b@1\"]",
+            Path::new("team.toml"),
+        )
+        .unwrap();
+        let personal = UserConfig::parse(
+            "[vscode]\nextensions = [\"Rust-Lang.Rust-Analyzer\", \"vadimcn.vscode-lldb\"]\nprune = true\nkeep = [\"ms-vscode.*\"]\n[vscode.profiles.rust]\nextensions = [\"A.B\", \"c.d\"]\n[vscode.profiles.dotnet]\nextensions = [\"e.f\"]",
+            Path::new("personal.toml"),
+        )
+        .unwrap();
+        team.merge(personal);
+        assert_eq!(
+            team.vscode.extensions,
+            vec![
+                "Rust-Lang.Rust-Analyzer",
+                "github.copilot-chat",
+                "vadimcn.vscode-lldb"
+            ]
+        );
+        assert_eq!(team.vscode.prune, Some(true));
+        assert_eq!(team.vscode.keep, vec!["ms-vscode.*"]);
+        assert_eq!(team.vscode.profiles["rust"].extensions, vec!["A.B", "c.d"]);
+        assert_eq!(team.vscode.profiles["dotnet"].extensions, vec!["e.f"]);
+    }
+
+    #[test]
+    fn test_parse_reports_file_and_line() {
+        let err = UserConfig::parse("[zsh]\ntheme = \"a\"\nplugin = []", Path::new("x.toml"))
+            .unwrap_err();
+        assert!(err.to_string().contains("x.toml:3"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_rejects_plugin_names_outside_plugins_dir() {
+        for name in ["../../x", "/tmp/x", ".hidden", "a/b"] {
+            let content = format!(
+                "[[zsh.external_plugins]]\nname = \"{}\"\nurl = \"https://example.com/a.git\"",
+                name
+            );
+            assert!(
+                UserConfig::parse(&content, Path::new("x.toml")).is_err(),
+                "{}",
+                name
+            );
+        }
+    }
+}
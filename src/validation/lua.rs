@@ -0,0 +1,271 @@
+//! Lua syntax check. `luac -p` is used when it is on PATH; otherwise a
+//! lightweight fallback tokenizes strings, comments and long brackets and
+//! verifies that blocks (`function`/`if`/`for`/`while`/`do` ... `end`,
+//! `repeat` ... `until`) and brackets are balanced. The fallback is a
+//! heuristic and misses errors a real parser would report.
+
+use crate::detectors::which::is_program_in_path;
+use std::{path::Path, process::Command};
+
+/// A syntax problem with its 1-based line number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LuaError {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Opener {
+    /// A block closed by `end`
+    Block(&'static str),
+    /// A `repeat` block closed by `until`
+    Repeat,
+    Bracket(u8),
+}
+
+/// Check the Lua file at `path` with `luac -p`, falling back to [`check_syntax`]
+pub fn check_file(path: &Path, source: &str) -> Result<(), LuaError> {
+    if !is_program_in_path("luac") {
+        return check_syntax(source);
+    }
+    let output = match Command::new("luac").arg("-p").arg(path).output() {
+        Ok(output) => output,
+        Err(_) => return check_syntax(source),
+    };
+    if output.status.success() {
+        return Ok(());
+    }
+    // luac reports `luac: <file>:<line>: <message>`
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let prefix = format!("luac: {}:", path.display());
+    let detail = stderr.trim().strip_prefix(&prefix).unwrap_or(stderr.trim());
+    match detail
+        .split_once(':')
+        .map(|(line, message)| (line.parse(), message))
+    {
+        Some((Ok(line), message)) => Err(LuaError {
+            line,
+            message: message.trim().to_string(),
+        }),
+        _ => Err(LuaError {
+            line: 1,
+            message: detail.to_string(),
+        }),
+    }
+}
+
+/// Check `source` for unbalanced blocks, brackets and unterminated strings or comments
+pub fn check_syntax(source: &str) -> Result<(), LuaError> {
+    let bytes = source.as_bytes();
+    let mut stack: Vec<(Opener, usize)> = Vec::new();
+    // `while` and `for` are followed by a `do` that belongs to the same block
+    let mut pending_do = 0usize;
+    let mut line = 1;
+    let mut i = 0;
+
+    let err = |line: usize, message: String| Err(LuaError { line, message });
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        match c {
+            b'\n' => {
+                line += 1;
+                i += 1;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                let start_line = line;
+                if let Some(level) = long_bracket_level(&bytes[i + 2..]) {
+                    match skip_long_bracket(bytes, i + 2, level, &mut line) {
+                        Some(end) => i = end,
+                        None => return err(start_line, "unterminated block comment".to_string()),
+                    }
+                } else {
+                    while i < bytes.len() && bytes[i] != b'\n' {
+                        i += 1;
+                    }
+                }
+            }
+            b'[' if long_bracket_level(&bytes[i..]).is_some() => {
+                let start_line = line;
+                let level = long_bracket_level(&bytes[i..]).unwrap_or_default();
+                match skip_long_bracket(bytes, i, level, &mut line) {
+                    Some(end) => i = end,
+                    None => return err(start_line, "unterminated long string".to_string()),
+                }
+            }
+            b'"' | b'\'' => {
+                let start_line = line;
+                i += 1;
+                loop {
+                    match bytes.get(i) {
+                        None | Some(b'\n') => {
+                            return err(start_line, "unterminated string".to_string());
+                        }
+                        Some(b'\\') => {
+                            if bytes.get(i + 1) == Some(&b'\n') {
+                                line += 1;
+                            }
+                            i += 2;
+                        }
+                        Some(&q) if q == c => {
+                            i += 1;
+                            break;
+                        }
+                        Some(_) => i += 1,
+                    }
+                }
+            }
+            b'(' | b'{' | b'[' => {
+                stack.push((Opener::Bracket(c), line));
+                i += 1;
+            }
+            b')' | b'}' | b']' => {
+                let expected = match c {
+                    b')' => b'(',
+                    b'}' => b'{',
+                    _ => b'[',
+                };
+                match stack.pop() {
+                    Some((Opener::Bracket(open), _)) if open == expected => {}
+                    Some((opener, open_line)) => {
+                        return err(
+                            line,
+                            format!(
+                                "unexpected '{}', {} from line {} is not closed",
+                                c as char,
+                                describe(opener),
+                                open_line
+                            ),
+                        );
+                    }
+                    None => return err(line, format!("unexpected '{}'", c as char)),
+                }
+                i += 1;
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                let start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                // Field names such as `wezterm.action.do` are not keywords
+                if start > 0
+                    && bytes[start - 1] == b'.'
+                    && bytes.get(start.wrapping_sub(2)) != Some(&b'.')
+                {
+                    continue;
+                }
+                match &source[start..i] {
+                    "function" => stack.push((Opener::Block("function"), line)),
+                    "if" => stack.push((Opener::Block("if"), line)),
+                    "while" => {
+                        stack.push((Opener::Block("while"), line));
+                        pending_do += 1;
+                    }
+                    "for" => {
+                        stack.push((Opener::Block("for"), line));
+                        pending_do += 1;
+                    }
+                    "do" if pending_do > 0 => pending_do -= 1,
+                    "do" => stack.push((Opener::Block("do"), line)),
+                    "repeat" => stack.push((Opener::Repeat, line)),
+                    keyword @ ("end" | "until") => {
+                        let expected_repeat = keyword == "until";
+                        match stack.pop() {
+                            Some((Opener::Repeat, _)) if expected_repeat => {}
+                            Some((Opener::Block(_), _)) if !expected_repeat => {}
+                            Some((opener, open_line)) => {
+                                return err(
+                                    line,
+                                    format!(
+                                        "unexpected '{}', {} from line {} is not closed",
+                                        keyword,
+                                        describe(opener),
+                                        open_line
+                                    ),
+                                );
+                            }
+                            None => return err(line, format!("unexpected '{}'", keyword)),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    match stack.pop() {
+        Some((opener, open_line)) => {
+            err(open_line, format!("{} is never closed", describe(opener)))
+        }
+        None => Ok(()),
+    }
+}
+
+fn describe(opener: Opener) -> String {
+    match opener {
+        Opener::Block(keyword) => format!("'{}'", keyword),
+        Opener::Repeat => "'repeat'".to_string(),
+        Opener::Bracket(c) => format!("'{}'", c as char),
+    }
+}
+
+/// Level of a long bracket (`[[`, `[==[`) starting at `bytes`, if any
+fn long_bracket_level(bytes: &[u8]) -> Option<usize> {
+    if bytes.first() != Some(&b'[') {
+        return None;
+    }
+    let level = bytes[1..].iter().take_while(|&&b| b == b'=').count();
+    (bytes.get(level + 1) == Some(&b'[')).then_some(level)
+}
+
+/// Skip a long bracket starting at `start`, returning the offset after its close
+fn skip_long_bracket(bytes: &[u8], start: usize, level: usize, line: &mut usize) -> Option<usize> {
+    let close: Vec<u8> = std::iter::once(b']')
+        .chain(std::iter::repeat_n(b'=', level))
+        .chain(std::iter::once(b']'))
+        .collect();
+    let mut i = start + level + 2;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(&close) {
+            return Some(i + close.len());
+        }
+        if bytes[i] == b'\n' {
+            *line += 1;
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_syntax_accepts_valid_config() {
+        let source = "local wezterm = require 'wezterm'\n\
+            --[[ block\ncomment ]]\n\
+            local function f(a)\n  if a:find 'x' then\n    return [[long]]\n  end\n  for i = 1, 3 do end\n  return a\nend\n\
+            return { key = \"w\" }\n";
+        assert_eq!(check_syntax(source), Ok(()));
+    }
+
+    #[test]
+    fn test_check_syntax_reports_unclosed_block() {
+        let err = check_syntax("local x = 1\nfunction f()\n  return x\n").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+
+    #[test]
+    fn test_check_syntax_reports_mismatched_bracket() {
+        let err = check_syntax("local t = {\n  a = (1,\n}\n").unwrap_err();
+        assert_eq!(err.line, 3);
+    }
+
+    #[test]
+    fn test_check_syntax_reports_unterminated_string() {
+        let err = check_syntax("local a = 1\nlocal s = 'abc\n").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod lua;
+pub mod preflight;
+
+pub use preflight::validate_assets;
@@ -0,0 +1,93 @@
+use crate::{
+    common::{Log, SetupError, SetupResult, jsonc, replace_home_with_tilde},
+    detectors::all_detectors,
+    validation::lua,
+};
+use std::{fs, path::Path};
+
+/// A problem found in a config asset; `line` is 1-based when known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetIssue {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// Check that every symlink and merge source exists and parses before anything is changed
+pub fn validate_assets(logger: &mut dyn Log, config_dir: &Path) -> SetupResult<()> {
+    logger.info("▶ Validate Config Assets");
+
+    let mut checked = 0;
+    let mut invalid = 0;
+    for detector in all_detectors() {
+        for source in detector.sources(config_dir) {
+            let display = replace_home_with_tilde(&source);
+            match check_asset(&source) {
+                Ok(()) => checked += 1,
+                Err(issue) => {
+                    invalid += 1;
+                    match issue.line {
+                        Some(line) => {
+                            logger.warn(&format!("{}:{}: {}", display, line, issue.message))
+                        }
+                        None => logger.warn(&format!("{}: {}", display, issue.message)),
+                    }
+                }
+            }
+        }
+    }
+
+    if invalid > 0 {
+        return Err(SetupError::Validation(format!(
+            "{} invalid config asset(s), nothing was changed",
+            invalid
+        )));
+    }
+
+    logger.ok_with_highlight("Config assets valid ->", &checked.to_string());
+    Ok(())
+}
+
+/// Check that an asset exists and parses according to its file type
+pub fn check_asset(path: &Path) -> Result<(), AssetIssue> {
+    let content = fs::read_to_string(path).map_err(|e| AssetIssue {
+        line: None,
+        message: if path.exists() {
+            format!("cannot be read: {}", e)
+        } else {
+            "file is missing".to_string()
+        },
+    })?;
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => content
+            .parse::<toml::Table>()
+            .map(|_| ())
+            .map_err(|e| AssetIssue {
+                line: e.span().map(|span| jsonc::line_col(&content, span.start).0),
+                message: e.message().to_string(),
+            }),
+        Some("json") => jsonc::parse(&content).map(|_| ()).map_err(|e| AssetIssue {
+            line: Some(e.line),
+            message: e.message,
+        }),
+        Some("lua") => lua::check_file(path, &content).map_err(|e| AssetIssue {
+            line: Some(e.line),
+            message: e.message,
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shipped_assets_are_valid() {
+        for detector in all_detectors() {
+            for source in detector.sources(Path::new("config")) {
+                assert_eq!(check_asset(&source), Ok(()), "{:?}", source);
+            }
+        }
+    }
+}